@@ -0,0 +1,10 @@
+CREATE TABLE epoch_checkpoints (
+  row_id SERIAL PRIMARY KEY,
+  process_id VARCHAR(255) NOT NULL REFERENCES processes(process_id),
+  epoch INTEGER NOT NULL,
+  root TEXT NOT NULL,
+  first_nonce INTEGER NOT NULL,
+  last_nonce INTEGER NOT NULL,
+  "timestamp" BIGINT NOT NULL,
+  UNIQUE (process_id, epoch)
+);
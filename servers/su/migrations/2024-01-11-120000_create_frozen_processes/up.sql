@@ -0,0 +1,4 @@
+CREATE TABLE frozen_processes (
+  row_id SERIAL PRIMARY KEY,
+  process_id VARCHAR(255) NOT NULL UNIQUE REFERENCES processes(process_id)
+);
@@ -0,0 +1,5 @@
+CREATE TABLE pending_uploads (
+  row_id SERIAL PRIMARY KEY,
+  item_id VARCHAR(255) NOT NULL UNIQUE,
+  bundle BYTEA NOT NULL
+);
@@ -0,0 +1,3 @@
+DROP INDEX idx_messages_process_id_owner;
+
+ALTER TABLE messages DROP COLUMN owner;
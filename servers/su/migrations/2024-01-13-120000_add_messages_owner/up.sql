@@ -0,0 +1,7 @@
+ALTER TABLE messages ADD COLUMN owner VARCHAR(255);
+
+UPDATE messages SET owner = message_data->'owner'->>'address';
+
+ALTER TABLE messages ALTER COLUMN owner SET NOT NULL;
+
+CREATE INDEX idx_messages_process_id_owner ON messages(process_id, owner);
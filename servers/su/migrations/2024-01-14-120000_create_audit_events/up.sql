@@ -0,0 +1,7 @@
+CREATE TABLE audit_events (
+  row_id SERIAL PRIMARY KEY,
+  actor VARCHAR(255) NOT NULL,
+  action VARCHAR(255) NOT NULL,
+  parameters JSONB NOT NULL,
+  "timestamp" BIGINT NOT NULL
+);
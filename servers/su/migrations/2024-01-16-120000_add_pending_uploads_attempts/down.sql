@@ -0,0 +1,2 @@
+ALTER TABLE pending_uploads DROP COLUMN last_error;
+ALTER TABLE pending_uploads DROP COLUMN attempts;
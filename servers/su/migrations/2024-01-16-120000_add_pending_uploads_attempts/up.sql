@@ -0,0 +1,2 @@
+ALTER TABLE pending_uploads ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_uploads ADD COLUMN last_error TEXT;
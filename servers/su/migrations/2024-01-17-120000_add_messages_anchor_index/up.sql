@@ -0,0 +1 @@
+CREATE INDEX idx_messages_anchor ON messages(process_id, owner, (message_data->'message'->>'anchor'));
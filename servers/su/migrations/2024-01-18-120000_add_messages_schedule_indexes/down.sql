@@ -0,0 +1,2 @@
+DROP INDEX idx_messages_process_id_timestamp;
+DROP INDEX idx_messages_process_id_nonce;
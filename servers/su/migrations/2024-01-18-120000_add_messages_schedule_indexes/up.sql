@@ -0,0 +1,5 @@
+-- nonce range reads and the latest message of a process
+CREATE INDEX idx_messages_process_id_nonce ON messages(process_id, nonce);
+
+-- the from/to timestamp window of the message listing
+CREATE INDEX idx_messages_process_id_timestamp ON messages(process_id, "timestamp");
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::domain::core::dal::{AuditSink, AuditEvent, DataStore};
+
+/*
+AuditSink used when admin actions are not audited,
+every event is dropped
+*/
+
+pub struct NoopAuditSink;
+
+impl NoopAuditSink {
+    pub fn init() -> Arc<dyn AuditSink> {
+        Arc::new(NoopAuditSink {})
+    }
+}
+
+#[async_trait]
+impl AuditSink for NoopAuditSink {
+    async fn record(&self, _event: &AuditEvent) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/*
+AuditSink keeping every event in the su database
+alongside the data it describes
+*/
+
+pub struct StoreAuditSink {
+    data_store: Arc<dyn DataStore>,
+}
+
+impl StoreAuditSink {
+    pub fn init(data_store: Arc<dyn DataStore>) -> Arc<dyn AuditSink> {
+        Arc::new(StoreAuditSink { data_store })
+    }
+}
+
+#[async_trait]
+impl AuditSink for StoreAuditSink {
+    async fn record(&self, event: &AuditEvent) -> Result<(), String> {
+        self.data_store.save_audit_event(event).await?;
+        Ok(())
+    }
+}
@@ -1,17 +1,103 @@
 
 use async_trait::async_trait;
 use reqwest::{Url, Client};
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 use tokio::sync::Mutex;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use arweave_rs::network::NetworkInfoClient;
+use bundlr_sdk::tags::Tag;
+use serde_json::json;
 use crate::domain::core::dal::{Gateway, NetworkInfo};
-use crate::domain::config::AoConfig;
 
 pub struct ArweaveGateway {
-    // Use Mutex to safely share and update state across tasks
-    height: Arc<Mutex<String>>,
-    current: Arc<Mutex<String>>,
+    gateway_url: String,
+    network_info: Arc<NetworkInfoCache>,
+}
+
+/*
+    where the network info comes from, split out from
+    the cache so the coalescing can be driven by a mock
+*/
+#[async_trait]
+trait NetworkInfoSource: Send + Sync {
+    async fn fetch(&self) -> Result<NetworkInfo, String>;
+}
+
+struct ArweaveNetworkInfo {
+    gateway_url: String,
+}
+
+#[async_trait]
+impl NetworkInfoSource for ArweaveNetworkInfo {
+    async fn fetch(&self) -> Result<NetworkInfo, String> {
+        ArweaveGateway::network_info_fetch_from(&self.gateway_url).await
+    }
+}
+
+/*
+    network info cached for a ttl. once it is stale
+    one caller refreshes it while the others are
+    served the stale value, only while it is cold do
+    callers queue so concurrent writers share a single
+    in flight fetch instead of each going to the gateway
+*/
+struct NetworkInfoCache {
+    source: Box<dyn NetworkInfoSource>,
+    ttl: Duration,
+    cached: std::sync::Mutex<Option<(Instant, NetworkInfo)>>,
+    refreshing: AtomicBool,
+    cold: Mutex<()>,
+}
+
+// clears the refreshing flag even when the refreshing caller is dropped mid fetch
+struct RefreshGuard<'a>(&'a AtomicBool);
+
+impl Drop for RefreshGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl NetworkInfoCache {
+    fn new(source: Box<dyn NetworkInfoSource>, ttl: Duration) -> Self {
+        NetworkInfoCache {
+            source,
+            ttl,
+            cached: std::sync::Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+            cold: Mutex::new(())
+        }
+    }
+
+    async fn get(&self) -> Result<NetworkInfo, String> {
+        let cached = self.cached.lock().unwrap().clone();
+        match cached {
+            Some((fetched_at, info)) if fetched_at.elapsed() < self.ttl => Ok(info),
+            Some((_, info)) => {
+                if self.refreshing.swap(true, Ordering::SeqCst) {
+                    return Ok(info);
+                }
+                let _refreshing = RefreshGuard(&self.refreshing);
+                let info = self.source.fetch().await?;
+                self.set(info.clone());
+                Ok(info)
+            },
+            None => {
+                let _cold = self.cold.lock().await;
+                if let Some((_, info)) = self.cached.lock().unwrap().as_ref() {
+                    return Ok(info.clone());
+                }
+                let info = self.source.fetch().await?;
+                self.set(info.clone());
+                Ok(info)
+            }
+        }
+    }
+
+    fn set(&self, info: NetworkInfo) {
+        *self.cached.lock().unwrap() = Some((Instant::now(), info));
+    }
 }
 
 #[derive(Debug)]
@@ -26,23 +112,21 @@ impl From<GatewayErrorType> for String {
 }
 
 impl ArweaveGateway {
-    pub async fn new() -> Result<Self, String> {
-        let network_info = ArweaveGateway::network_info_fetch().await?;
-
-        let height = Arc::new(Mutex::new(network_info.height.clone()));
-        let current = Arc::new(Mutex::new(network_info.current.clone()));
+    pub async fn new(gateway_url: String, network_info_ttl: Duration) -> Result<Self, String> {
+        let network_info = Arc::new(
+            NetworkInfoCache::new(Box::new(ArweaveNetworkInfo { gateway_url: gateway_url.clone() }), network_info_ttl)
+        );
+        network_info.get().await?;
 
-        let gateway = ArweaveGateway { height: height.clone(), current: current.clone() };
+        let gateway = ArweaveGateway { gateway_url: gateway_url.clone(), network_info: network_info.clone() };
 
         // Spawn a background task to refresh network info every 1 minute
         tokio::spawn(async move {
             loop {
                 sleep(Duration::from_secs(5)).await;
-                if let Ok(updated_info) = ArweaveGateway::network_info_fetch().await {
-                    let mut height_lock = height.lock().await;
-                    *height_lock = updated_info.height.clone();
-                    let mut current_lock = current.lock().await;
-                    *current_lock = updated_info.current.clone();
+                // fetched outside the cache lock so writers keep being served meanwhile
+                if let Ok(updated_info) = ArweaveGateway::network_info_fetch_from(&gateway_url).await {
+                    network_info.set(updated_info);
                 }
             }
         });
@@ -50,10 +134,8 @@ impl ArweaveGateway {
         Ok(gateway)
     }
 
-    async fn network_info_fetch() -> Result<NetworkInfo, String> {
-        let config = AoConfig::new(Some("su".to_string())).expect("Failed to read configuration");
-        let gateway_url = config.gateway_url;
-        let url = Url::parse(&gateway_url).map_err(|e| format!("{:?}", e))?;
+    async fn network_info_fetch_from(gateway_url: &str) -> Result<NetworkInfo, String> {
+        let url = Url::parse(gateway_url).map_err(|e| format!("{:?}", e))?;
     
         let network_client = NetworkInfoClient::new(url);
     
@@ -88,10 +170,7 @@ impl ArweaveGateway {
 #[async_trait]
 impl Gateway for ArweaveGateway {
     async fn check_head(&self, tx_id: String) -> Result<bool, String> {
-        let config = AoConfig::new(Some("su".to_string())).expect("Failed to read configuration");
-        let gateway_url = config.gateway_url;
-
-        let url = match Url::parse(&gateway_url) {
+        let url = match Url::parse(&self.gateway_url) {
             Ok(u) => u,
             Err(e) => return Err(format!("{}", e))
         };
@@ -118,8 +197,126 @@ impl Gateway for ArweaveGateway {
     }
 
     async fn network_info(&self) -> Result<NetworkInfo, String> {
-        let height = self.height.lock().await.clone();
-        let current = self.current.lock().await.clone();
-        Ok(NetworkInfo { height, current })
+        self.network_info.get().await
+    }
+
+    // hinted gateways are rare so they are fetched uncached
+    async fn network_info_from(&self, gateway_url: &str) -> Result<NetworkInfo, String> {
+        ArweaveGateway::network_info_fetch_from(gateway_url).await
+    }
+
+    async fn block_timestamp(&self, height: i64) -> Result<i64, String> {
+        let url = Url::parse(&self.gateway_url)
+            .and_then(|url| url.join(&format!("block/height/{}", height)))
+            .map_err(|e| format!("{:?}", e))?;
+
+        let response = Client::new()
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch block {}: {:?}", height, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch block {}: {}", height, response.status()));
+        }
+
+        let body = response.text().await.map_err(|e| format!("{:?}", e))?;
+        let block: serde_json::Value = serde_json::from_str(&body).map_err(|e| format!("{:?}", e))?;
+        block["timestamp"].as_i64().ok_or(format!("Block {} has no timestamp", height))
+    }
+
+    async fn tx_tags(&self, tx_id: &str) -> Result<Vec<Tag>, String> {
+        let url = Url::parse(&self.gateway_url)
+            .and_then(|url| url.join("graphql"))
+            .map_err(|e| format!("{:?}", e))?;
+        let query = json!({
+            "query": "query ($id: ID!) { transaction(id: $id) { tags { name value } } }",
+            "variables": { "id": tx_id }
+        });
+
+        let response = Client::new()
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(query.to_string())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch tags of {}: {:?}", tx_id, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch tags of {}: {}", tx_id, response.status()));
+        }
+
+        let body = response.text().await.map_err(|e| format!("{:?}", e))?;
+        let result: serde_json::Value = serde_json::from_str(&body).map_err(|e| format!("{:?}", e))?;
+        let tags = result["data"]["transaction"]["tags"].as_array()
+            .ok_or(format!("Transaction {} not found", tx_id))?;
+        Ok(tags.iter()
+            .filter_map(|tag| Some(Tag::new(tag["name"].as_str()?, tag["value"].as_str()?)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    // counts fetches and holds each one open long enough for callers to pile up
+    struct CountingSource {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl NetworkInfoSource for CountingSource {
+        async fn fetch(&self) -> Result<NetworkInfo, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            sleep(Duration::from_millis(50)).await;
+            Ok(NetworkInfo { height: "000000001000".to_string(), current: "current".to_string() })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_network_info_coalesces_cold_fetches() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(NetworkInfoCache::new(
+            Box::new(CountingSource { calls: calls.clone() }),
+            Duration::from_secs(60)
+        ));
+
+        let writers: Vec<_> = (0..16).map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.get().await })
+        }).collect();
+
+        for writer in writers {
+            let info = writer.await.unwrap().unwrap();
+            assert_eq!(info.height, "000000001000");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_network_info_serves_stale_while_refreshing() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(NetworkInfoCache::new(
+            Box::new(CountingSource { calls: calls.clone() }),
+            Duration::ZERO
+        ));
+        cache.set(NetworkInfo { height: "000000000999".to_string(), current: "stale".to_string() });
+
+        let writers: Vec<_> = (0..16).map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.get().await })
+        }).collect();
+
+        let mut heights = vec![];
+        for writer in writers {
+            heights.push(writer.await.unwrap().unwrap().height);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(heights.iter().filter(|height| *height == "000000001000").count(), 1);
+        assert_eq!(heights.iter().filter(|height| *height == "000000000999").count(), 15);
+
+        // the flag is cleared so the next stale read refreshes again
+        cache.get().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
     }
 }
\ No newline at end of file
@@ -20,6 +20,8 @@ table! {
         timestamp -> BigInt,
         bundle -> Bytea,
         hash_chain -> Text,
+        owner -> Varchar,
+        compressed -> Bool,
     }
 }
 
@@ -39,9 +41,52 @@ table! {
     }
 }
 
+table! {
+    epoch_checkpoints (row_id) {
+        row_id -> Int4,
+        process_id -> Varchar,
+        epoch -> Int4,
+        root -> Text,
+        first_nonce -> Int4,
+        last_nonce -> Int4,
+        timestamp -> BigInt,
+    }
+}
+
+table! {
+    frozen_processes (row_id) {
+        row_id -> Int4,
+        process_id -> Varchar,
+    }
+}
+
+table! {
+    pending_uploads (row_id) {
+        row_id -> Int4,
+        item_id -> Varchar,
+        bundle -> Bytea,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+    }
+}
+
+table! {
+    audit_events (row_id) {
+        row_id -> Int4,
+        actor -> Varchar,
+        action -> Varchar,
+        parameters -> Jsonb,
+        timestamp -> BigInt,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
     processes,
     messages,
+    epoch_checkpoints,
+    frozen_processes,
+    pending_uploads,
+    audit_events,
     schedulers,
     process_schedulers,
 );
@@ -1,5 +1,7 @@
+use std::borrow::Cow;
 use std::env::VarError;
 
+use async_trait::async_trait;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::ConnectionManager;
@@ -14,8 +16,17 @@ use super::super::core::dal::{
     Process, 
     PaginatedMessages, 
     JsonErrorType, 
+    cursor_position,
+    cursor_timestamp,
     Scheduler, 
-    ProcessScheduler
+    ProcessScheduler,
+    MessageBundle,
+    EpochCheckpoint,
+    PendingUpload,
+    PendingUploadStatus,
+    OwnerStats,
+    AuditEvent,
+    SortOrder
 };
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
@@ -65,14 +76,56 @@ impl From<std::num::ParseIntError> for StoreErrorType {
 }
 
 
+#[derive(Clone)]
 pub struct StoreClient{
-    pool: Pool<ConnectionManager<PgConnection>>
+    pool: Pool<ConnectionManager<PgConnection>>,
+    compress_binaries: bool
 }
 
+const COMPRESSION_LEVEL: i32 = 3;
+
+// the binary as written to a row, compressed when compress is set
+fn encode_binary(binary: &[u8], compress: bool) -> Result<Cow<'_, [u8]>, StoreErrorType> {
+    match compress {
+        true => zstd::encode_all(binary, COMPRESSION_LEVEL)
+            .map(Cow::Owned)
+            .map_err(|e| StoreErrorType::DatabaseError(format!("binary compression error: {}", e))),
+        false => Ok(Cow::Borrowed(binary))
+    }
+}
+
+/*
+    the original binary of a row, rows written before
+    compression was enabled have compressed false and
+    are returned as they are
+*/
+fn decode_binary(stored: Vec<u8>, compressed: bool) -> Result<Vec<u8>, StoreErrorType> {
+    match compressed {
+        true => zstd::decode_all(&stored[..])
+            .map_err(|e| StoreErrorType::DatabaseError(format!("binary decompression error: {}", e))),
+        false => Ok(stored)
+    }
+}
+
+/*
+    a message waits on an upload while a pending upload
+    holds its bundle. the messages of a batch share one
+    bundle tracked under the id of the first, so the
+    rest are found by the bundle stored with them
+*/
+const PENDING_BUNDLE: &str = "messages.bundle IN (
+    SELECT pending_messages.bundle FROM messages AS pending_messages
+    JOIN pending_uploads ON pending_uploads.item_id = pending_messages.message_id
+)";
+
 impl StoreClient {
     pub fn new() -> Result<Self, StoreErrorType> {
         let config = AoConfig::new(Some("su".to_string())).expect("Failed to read configuration");
-        let database_url = config.database_url;
+        Self::connect(&config.database_url, config.compress_binaries)
+    }
+
+    // a client for the given database, without reading the server configuration
+    pub fn connect(database_url: &str, compress_binaries: bool) -> Result<Self, StoreErrorType> {
         let manager = ConnectionManager::<PgConnection>::new(database_url);
         let pool = Pool::builder()
             .test_on_check_out(true)
@@ -80,7 +133,7 @@ impl StoreClient {
                 |_| StoreErrorType::DatabaseError("Failed to initialize connection pool.".to_string())
             )?;
 
-        Ok(StoreClient { pool })
+        Ok(StoreClient { pool, compress_binaries })
     }
 
     pub fn get_conn(&self) -> Result<diesel::r2d2::PooledConnection<ConnectionManager<PgConnection>>, StoreErrorType> {
@@ -103,7 +156,8 @@ impl StoreClient {
     }
 }
 
-impl DataStore for StoreClient {
+// the queries, each blocking on a pooled connection
+impl StoreClient {
     fn save_process(&self, process: &Process, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
         use super::schema::processes::dsl::*;
         let conn = &mut self.get_conn()?;
@@ -146,9 +200,67 @@ impl DataStore for StoreClient {
         }
     }
     
+    fn get_process_binary(&self, process_id_in: &str) -> Result<Vec<u8>, StoreErrorType> {
+        use super::schema::processes::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let bundle_result: Result<Option<Vec<u8>>, DieselError> = processes
+            .filter(process_id.eq(process_id_in))
+            .select(bundle)
+            .first(conn)
+            .optional();
+
+        match bundle_result {
+            Ok(Some(bundle_out)) => Ok(bundle_out),
+            Ok(None) => Err(StoreErrorType::NotFound("Process not found".to_string())),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
     fn save_message(&self, message: &Message, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        let conn = &mut self.get_conn()?;
+        self.insert_message(conn, message, bundle_in)
+    }
+
+    fn delete_process(&self, process_id_in: &str) -> Result<String, StoreErrorType> {
+        use super::schema::processes::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match diesel::delete(processes.filter(process_id.eq(process_id_in))).execute(conn) {
+            Ok(_) => Ok("deleted".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn delete_message(&self, message_id_in: &str) -> Result<String, StoreErrorType> {
         use super::schema::messages::dsl::*;
         let conn = &mut self.get_conn()?;
+
+        match diesel::delete(messages.filter(message_id.eq(message_id_in))).execute(conn) {
+            Ok(_) => Ok("deleted".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    /*
+        save every message in one transaction, a failure on
+        any of them rolls back the ones before it. the next
+        schedule lock reads the latest stored message so the
+        nonce does not advance past a rolled back batch
+    */
+    fn save_messages(&self, items: &[(Message, Vec<u8>)]) -> Result<String, StoreErrorType> {
+        let conn = &mut self.get_conn()?;
+        conn.transaction::<_, StoreErrorType, _>(|conn| {
+            for (message, bundle_in) in items {
+                self.insert_message(conn, message, bundle_in)?;
+            }
+            Ok("saved".to_string())
+        })
+    }
+
+    fn insert_message(&self, conn: &mut PgConnection, message: &Message, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let stored_bundle = encode_binary(bundle_in, self.compress_binaries)?;
     
         let new_message = NewMessage {
             process_id: &message.process_id,
@@ -157,8 +269,10 @@ impl DataStore for StoreClient {
             epoch: &message.epoch,
             nonce: &message.nonce,
             timestamp: &message.timestamp,
-            bundle: bundle_in,
+            bundle: &stored_bundle,
             hash_chain: &message.hash_chain,
+            owner: &message.owner.address,
+            compressed: self.compress_binaries,
         };
     
         match diesel::insert_into(messages)
@@ -185,29 +299,48 @@ impl DataStore for StoreClient {
         from: &Option<String>,
         to: &Option<String>,
         limit: &Option<i32>,
+        order: &Option<SortOrder>,
     ) -> Result<PaginatedMessages, StoreErrorType> {
         use super::schema::messages::dsl::*;
         let conn = &mut self.get_conn()?;
+        let descending = order.unwrap_or_default() == SortOrder::Desc;
         let mut query = messages
             .filter(process_id.eq(process_id_in))
             .into_boxed();
     
-        // Apply 'from' timestamp filtering if 'from' is provided
+        // Apply 'from' timestamp filtering if 'from' is provided, either a timestamp or a next_cursor
+        // it is exclusive and where the listing starts, so an upper bound when descending
+        // a cursor also carries the nonce, which orders the messages of a batch that share a timestamp
         if let Some(from_timestamp_str) = from {
-            let from_timestamp = from_timestamp_str.parse::<i64>().map_err(StoreErrorType::from)?;
-            query = query.filter(timestamp.gt(from_timestamp));
+            let (from_timestamp, from_nonce) = cursor_position(from_timestamp_str).map_err(StoreErrorType::from)?;
+            query = match (descending, from_nonce) {
+                (true, Some(from_nonce)) => query.filter(
+                    timestamp.lt(from_timestamp).or(timestamp.eq(from_timestamp).and(nonce.lt(from_nonce)))
+                ),
+                (false, Some(from_nonce)) => query.filter(
+                    timestamp.gt(from_timestamp).or(timestamp.eq(from_timestamp).and(nonce.gt(from_nonce)))
+                ),
+                (true, None) => query.filter(timestamp.lt(from_timestamp)),
+                (false, None) => query.filter(timestamp.gt(from_timestamp))
+            };
         }
     
-        // Apply 'to' timestamp filtering if 'to' is provided
+        // Apply 'to' timestamp filtering if 'to' is provided, inclusive and where the listing ends
         if let Some(to_timestamp_str) = to {
-            let to_timestamp = to_timestamp_str.parse::<i64>().map_err(StoreErrorType::from)?;
-            query = query.filter(timestamp.le(to_timestamp));
+            let to_timestamp = cursor_timestamp(to_timestamp_str).map_err(StoreErrorType::from)?;
+            query = match descending {
+                true => query.filter(timestamp.ge(to_timestamp)),
+                false => query.filter(timestamp.le(to_timestamp))
+            };
         }
     
         // Apply limit, converting Option<i32> to i64 and adding 1 to check for the next page
         let limit_val = limit.unwrap_or(5000) as i64; // Default limit if none is provided
+        query = match descending {
+            true => query.order((timestamp.desc(), nonce.desc())),
+            false => query.order((timestamp.asc(), nonce.asc()))
+        };
         let db_messages_result: Result<Vec<DbMessage>, DieselError> = query
-            .order(timestamp.asc())
             .limit(limit_val + 1) // Fetch one extra record to determine if a next page exists
             .load(conn);
     
@@ -253,14 +386,526 @@ impl DataStore for StoreClient {
         }
     }
 
+    fn get_owner_stats(&self, process_id_in: &str) -> Result<Vec<OwnerStats>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .group_by(owner)
+            .select((owner, diesel::dsl::count_star(), diesel::dsl::min(timestamp), diesel::dsl::max(timestamp)))
+            .order(owner.asc())
+            .load::<(String, i64, Option<i64>, Option<i64>)>(conn)
+        {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|(owner_out, count_out, first_out, last_out)| OwnerStats {
+                    owner: owner_out,
+                    message_count: count_out,
+                    first_timestamp: first_out.unwrap_or_default(),
+                    last_timestamp: last_out.unwrap_or_default()
+                })
+                .collect()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_message_binary(&self, message_id_in: &str) -> Result<Vec<u8>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let bundle_result: Result<Option<(Vec<u8>, bool)>, DieselError> = messages
+            .filter(message_id.eq(message_id_in))
+            .select((bundle, compressed))
+            .first(conn)
+            .optional();
+
+        match bundle_result {
+            Ok(Some((bundle_out, compressed_out))) => decode_binary(bundle_out, compressed_out),
+            Ok(None) => Err(StoreErrorType::NotFound("Message not found".to_string())),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_message_ids(&self, process_id_in: &str) -> Result<Vec<String>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .order(row_id.asc())
+            .select(message_id)
+            .load::<String>(conn)
+        {
+            Ok(ids) => Ok(ids),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn ping(&self) -> Result<(), StoreErrorType> {
+        let conn = &mut self.get_conn()?;
+        match diesel::sql_query("SELECT 1").execute(conn) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_message_count(&self, process_id_in: &str) -> Result<i64, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .count()
+            .get_result::<i64>(conn)
+        {
+            Ok(count_out) => Ok(count_out),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_oldest_pending_timestamp(&self, process_id_in: &str) -> Result<Option<i64>, StoreErrorType> {
+        use super::schema::messages;
+        use diesel::sql_types::Bool;
+        let conn = &mut self.get_conn()?;
+
+        match messages::table
+            .filter(messages::process_id.eq(process_id_in))
+            .filter(diesel::dsl::sql::<Bool>(PENDING_BUNDLE))
+            .select(diesel::dsl::min(messages::timestamp))
+            .first::<Option<i64>>(conn)
+        {
+            Ok(timestamp_out) => Ok(timestamp_out),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn anchor_used(&self, process_id_in: &str, owner_in: &str, anchor_in: &str) -> Result<bool, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        use diesel::sql_types::{Bool, Text};
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .filter(owner.eq(owner_in))
+            .filter(diesel::dsl::sql::<Bool>("message_data->'message'->>'anchor' = ").bind::<Text, _>(anchor_in))
+            .count()
+            .get_result::<i64>(conn)
+        {
+            Ok(count_out) => Ok(count_out > 0),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn message_ids_unique(&self) -> Result<bool, StoreErrorType> {
+        use diesel::sql_types::Bool;
+        let conn = &mut self.get_conn()?;
+
+        // the UNIQUE constraint of the initial migration, an invalid index enforces nothing
+        match diesel::select(diesel::dsl::sql::<Bool>("EXISTS (
+            SELECT 1 FROM pg_index
+            JOIN pg_class ON pg_class.oid = pg_index.indrelid
+            JOIN pg_attribute ON pg_attribute.attrelid = pg_class.oid AND pg_attribute.attnum = pg_index.indkey[0]
+            WHERE pg_class.relname = 'messages'
+            AND pg_index.indisunique AND pg_index.indisvalid
+            AND pg_index.indnatts = 1 AND pg_attribute.attname = 'message_id'
+        )")).get_result::<bool>(conn)
+        {
+            Ok(unique) => Ok(unique),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn find_duplicate_nonces(&self, process_id_in: &str) -> Result<Vec<i32>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        use diesel::dsl::count;
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .group_by((process_id, nonce))
+            .having(count(row_id).gt(1))
+            .select(nonce)
+            .order(nonce.asc())
+            .load::<i32>(conn)
+        {
+            Ok(nonces) => Ok(nonces),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_messages_by_nonce_range(
+        &self,
+        process_id_in: &str,
+        from_nonce: i32,
+        to_nonce: i32,
+    ) -> Result<Vec<Message>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        // both bounds are inclusive
+        let db_messages_result: Result<Vec<DbMessage>, DieselError> = messages
+            .filter(process_id.eq(process_id_in))
+            .filter(nonce.ge(from_nonce))
+            .filter(nonce.le(to_nonce))
+            .order(nonce.asc())
+            .load(conn);
+
+        match db_messages_result {
+            Ok(db_messages) => {
+                db_messages
+                    .iter()
+                    .map(|db_message| serde_json::from_value(db_message.message_data.clone()).map_err(StoreErrorType::from))
+                    .collect()
+            },
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_epoch_hash_chains(&self, process_id_in: &str, epoch_in: i32, after_nonce: Option<i32>) -> Result<Vec<(i32, String)>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        // nonces start at 0 so without a nonce every chain follows -1
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .filter(epoch.eq(epoch_in))
+            .filter(nonce.gt(after_nonce.unwrap_or(-1)))
+            .order(nonce.asc())
+            .select((nonce, hash_chain))
+            .load::<(i32, String)>(conn)
+        {
+            Ok(rows) => Ok(rows),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_epoch_message_counts(&self, process_id_in: &str) -> Result<Vec<(i32, i64)>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match messages
+            .filter(process_id.eq(process_id_in))
+            .group_by(epoch)
+            .select((epoch, diesel::dsl::count_star()))
+            .order(epoch.asc())
+            .load::<(i32, i64)>(conn)
+        {
+            Ok(rows) => Ok(rows),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn save_epoch_checkpoint(&self, checkpoint: &EpochCheckpoint) -> Result<String, StoreErrorType> {
+        use super::schema::epoch_checkpoints::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let new_checkpoint = NewEpochCheckpoint {
+            process_id: &checkpoint.process_id,
+            epoch: &checkpoint.epoch,
+            root: &checkpoint.root,
+            first_nonce: &checkpoint.first_nonce,
+            last_nonce: &checkpoint.last_nonce,
+            timestamp: &checkpoint.timestamp,
+        };
+
+        // a closed epoch never changes so an existing checkpoint is kept
+        match diesel::insert_into(epoch_checkpoints)
+            .values(&new_checkpoint)
+            .on_conflict((process_id, epoch))
+            .do_nothing()
+            .execute(conn)
+        {
+            Ok(_) => Ok("saved".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_epoch_checkpoint(&self, process_id_in: &str, epoch_in: i32) -> Result<EpochCheckpoint, StoreErrorType> {
+        use super::schema::epoch_checkpoints::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let db_checkpoint_result: Result<Option<DbEpochCheckpoint>, DieselError> = epoch_checkpoints
+            .filter(process_id.eq(process_id_in))
+            .filter(epoch.eq(epoch_in))
+            .select(DbEpochCheckpoint::as_select())
+            .first(conn)
+            .optional();
+
+        match db_checkpoint_result {
+            Ok(Some(db_checkpoint)) => Ok(EpochCheckpoint {
+                process_id: db_checkpoint.process_id,
+                epoch: db_checkpoint.epoch,
+                root: db_checkpoint.root,
+                first_nonce: db_checkpoint.first_nonce,
+                last_nonce: db_checkpoint.last_nonce,
+                timestamp: db_checkpoint.timestamp,
+            }),
+            Ok(None) => Err(StoreErrorType::NotFound("Epoch checkpoint not found".to_string())),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn set_process_frozen(&self, process_id_in: &str, frozen: bool) -> Result<String, StoreErrorType> {
+        use super::schema::frozen_processes::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let result = if frozen {
+            diesel::insert_into(frozen_processes)
+                .values(&NewFrozenProcess { process_id: process_id_in })
+                .on_conflict(process_id)
+                .do_nothing()
+                .execute(conn)
+        } else {
+            diesel::delete(frozen_processes.filter(process_id.eq(process_id_in)))
+                .execute(conn)
+        };
+
+        match result {
+            Ok(_) => Ok("saved".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn is_process_frozen(&self, process_id_in: &str) -> Result<bool, StoreErrorType> {
+        use super::schema::frozen_processes::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match diesel::select(diesel::dsl::exists(
+            frozen_processes.filter(process_id.eq(process_id_in))
+        )).get_result(conn) {
+            Ok(frozen) => Ok(frozen),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn save_pending_upload(&self, item_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        use super::schema::pending_uploads::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let new_pending_upload = NewPendingUpload {
+            item_id: item_id_in,
+            bundle: bundle_in,
+        };
+
+        match diesel::insert_into(pending_uploads)
+            .values(&new_pending_upload)
+            .on_conflict(item_id)
+            .do_nothing()
+            .execute(conn)
+        {
+            Ok(_) => Ok("saved".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_pending_uploads(&self, after_row_id: i32, max_attempts: i32, limit: i32) -> Result<Vec<PendingUpload>, StoreErrorType> {
+        use super::schema::pending_uploads::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match pending_uploads
+            .filter(row_id.gt(after_row_id))
+            .filter(attempts.lt(max_attempts))
+            .order(row_id.asc())
+            .limit(limit as i64)
+            .select((row_id, item_id, bundle))
+            .load::<(i32, String, Vec<u8>)>(conn)
+        {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|(row_id_out, item_id_out, bundle_out)| PendingUpload {
+                    row_id: row_id_out,
+                    item_id: item_id_out,
+                    bundle: bundle_out
+                })
+                .collect()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn delete_pending_upload(&self, item_id_in: &str) -> Result<String, StoreErrorType> {
+        use super::schema::pending_uploads::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match diesel::delete(pending_uploads.filter(item_id.eq(item_id_in))).execute(conn) {
+            Ok(_) => Ok("deleted".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn record_upload_failure(&self, item_id_in: &str, error: &str) -> Result<String, StoreErrorType> {
+        use super::schema::pending_uploads::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        match diesel::update(pending_uploads.filter(item_id.eq(item_id_in)))
+            .set((attempts.eq(attempts + 1), last_error.eq(error)))
+            .execute(conn)
+        {
+            Ok(_) => Ok("recorded".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_pending_upload_statuses(
+        &self,
+        after_row_id: i32,
+        to_row_id: Option<i32>,
+        limit: i32
+    ) -> Result<Vec<PendingUploadStatus>, StoreErrorType> {
+        use super::schema::pending_uploads::dsl::*;
+        let conn = &mut self.get_conn()?;
+        let mut query = pending_uploads
+            .filter(row_id.gt(after_row_id))
+            .into_boxed();
+
+        if let Some(to_row_id) = to_row_id {
+            query = query.filter(row_id.le(to_row_id));
+        }
+
+        match query
+            .order(row_id.asc())
+            .limit(limit as i64)
+            .select((row_id, item_id, attempts, last_error))
+            .load::<(i32, String, i32, Option<String>)>(conn)
+        {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|(row_id_out, item_id_out, attempts_out, last_error_out)| PendingUploadStatus {
+                    row_id: row_id_out,
+                    item_id: item_id_out,
+                    attempts: attempts_out,
+                    last_error: last_error_out
+                })
+                .collect()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_latest_uploaded_bundle(&self, process_id_in: &str) -> Result<Option<MessageBundle>, StoreErrorType> {
+        use super::schema::messages;
+        use diesel::sql_types::Bool;
+        let conn = &mut self.get_conn()?;
+
+        match messages::table
+            .filter(messages::process_id.eq(process_id_in))
+            .filter(diesel::dsl::not(diesel::dsl::sql::<Bool>(PENDING_BUNDLE)))
+            .order(messages::row_id.desc())
+            .select((messages::row_id, messages::message_id, messages::timestamp, messages::bundle, messages::compressed))
+            .first::<(i32, String, i64, Vec<u8>, bool)>(conn)
+            .optional()
+        {
+            Ok(Some((row_id_out, message_id_out, timestamp_out, bundle_out, compressed_out))) => Ok(Some(MessageBundle {
+                row_id: row_id_out,
+                message_id: message_id_out,
+                timestamp: timestamp_out,
+                bundle: decode_binary(bundle_out, compressed_out)?
+            })),
+            Ok(None) => Ok(None),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn count_pending_uploads(&self, process_id_in: &str) -> Result<i64, StoreErrorType> {
+        use super::schema::messages;
+        use diesel::sql_types::Bool;
+        let conn = &mut self.get_conn()?;
+
+        match messages::table
+            .filter(messages::process_id.eq(process_id_in))
+            .filter(diesel::dsl::sql::<Bool>(PENDING_BUNDLE))
+            .count()
+            .get_result::<i64>(conn)
+        {
+            Ok(count_out) => Ok(count_out),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn save_audit_event(&self, event: &AuditEvent) -> Result<String, StoreErrorType> {
+        use super::schema::audit_events::dsl::*;
+        let conn = &mut self.get_conn()?;
+
+        let new_event = NewAuditEvent {
+            actor: &event.actor,
+            action: &event.action,
+            parameters: &event.parameters,
+            timestamp: &event.timestamp,
+        };
+
+        match diesel::insert_into(audit_events)
+            .values(&new_event)
+            .execute(conn)
+        {
+            Ok(_) => Ok("saved".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn get_message_bundles(
+        &self,
+        from: &Option<String>,
+        to: &Option<String>,
+        after_row_id: i32,
+        limit: i32,
+    ) -> Result<Vec<MessageBundle>, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+        let mut query = messages
+            .filter(row_id.gt(after_row_id))
+            .into_boxed();
+
+        if let Some(from_timestamp_str) = from {
+            let from_timestamp = from_timestamp_str.parse::<i64>().map_err(StoreErrorType::from)?;
+            query = query.filter(timestamp.gt(from_timestamp));
+        }
+
+        if let Some(to_timestamp_str) = to {
+            let to_timestamp = to_timestamp_str.parse::<i64>().map_err(StoreErrorType::from)?;
+            query = query.filter(timestamp.le(to_timestamp));
+        }
+
+        match query
+            .order(row_id.asc())
+            .limit(limit as i64)
+            .select((row_id, message_id, timestamp, bundle, compressed))
+            .load::<(i32, String, i64, Vec<u8>, bool)>(conn)
+        {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|(row_id_out, message_id_out, timestamp_out, bundle_out, compressed_out)| Ok(MessageBundle {
+                    row_id: row_id_out,
+                    message_id: message_id_out,
+                    timestamp: timestamp_out,
+                    bundle: decode_binary(bundle_out, compressed_out)?
+                }))
+                .collect(),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
+    fn update_message_bundle(&self, message_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        use super::schema::messages::dsl::*;
+        let conn = &mut self.get_conn()?;
+        let stored_bundle = encode_binary(bundle_in, self.compress_binaries)?;
+
+        match diesel::update(messages.filter(message_id.eq(message_id_in)))
+            .set((bundle.eq(&stored_bundle[..]), compressed.eq(self.compress_binaries)))
+            .execute(conn)
+        {
+            Ok(0) => Err(StoreErrorType::NotFound("Message not found".to_string())),
+            Ok(_) => Ok("updated".to_string()),
+            Err(e) => Err(StoreErrorType::from(e)),
+        }
+    }
+
     fn get_latest_message(&self, process_id_in: &str) -> Result<Option<Message>, StoreErrorType> {
         use super::schema::messages::dsl::*;
         let conn = &mut self.get_conn()?;
     
-        // Get the latest DbMessage
+        // the highest nonce is the most recently scheduled message
         let latest_db_message_result = messages
             .filter(process_id.eq(process_id_in))
-            .order(row_id.desc())
+            .order(nonce.desc())
             .first::<DbMessage>(conn);
     
         match latest_db_message_result {
@@ -384,7 +1029,7 @@ impl DataStore for StoreClient {
         }
     }
 
-    fn get_scheduler_by_url(&self, url_in: &String) -> Result<Scheduler, StoreErrorType> {
+    fn get_scheduler_by_url(&self, url_in: &str) -> Result<Scheduler, StoreErrorType> {
         use super::schema::schedulers::dsl::*;
         let conn = &mut self.get_conn()?;
     
@@ -427,6 +1072,237 @@ impl DataStore for StoreClient {
     }
 }
 
+/*
+    diesel blocks on its connection, so each query
+    runs on the blocking pool rather than stalling a
+    runtime thread that is serving other requests
+*/
+impl StoreClient {
+    async fn blocking<T, F>(&self, query: F) -> Result<T, StoreErrorType>
+    where
+        T: Send + 'static,
+        F: FnOnce(&StoreClient) -> Result<T, StoreErrorType> + Send + 'static
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || query(&store))
+            .await
+            .map_err(|e| StoreErrorType::DatabaseError(format!("Blocking query failed: {}", e)))?
+    }
+}
+
+#[async_trait]
+impl DataStore for StoreClient {
+    async fn save_process(&self, process: &Process, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        let (process, bundle_in) = (process.clone(), bundle_in.to_vec());
+        self.blocking(move |store| store.save_process(&process, &bundle_in)).await
+    }
+
+    async fn get_process(&self, process_id_in: &str) -> Result<Process, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_process(&process_id_in)).await
+    }
+
+    async fn get_process_binary(&self, process_id_in: &str) -> Result<Vec<u8>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_process_binary(&process_id_in)).await
+    }
+
+    async fn save_message(&self, message: &Message, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        let (message, bundle_in) = (message.clone(), bundle_in.to_vec());
+        self.blocking(move |store| store.save_message(&message, &bundle_in)).await
+    }
+
+    async fn delete_process(&self, process_id_in: &str) -> Result<String, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.delete_process(&process_id_in)).await
+    }
+
+    async fn delete_message(&self, message_id_in: &str) -> Result<String, StoreErrorType> {
+        let message_id_in = message_id_in.to_string();
+        self.blocking(move |store| store.delete_message(&message_id_in)).await
+    }
+
+    async fn get_messages(&self, process_id_in: &str, from: &Option<String>, to: &Option<String>, limit: &Option<i32>, order: &Option<SortOrder>) -> Result<PaginatedMessages, StoreErrorType> {
+        let (process_id_in, from, to, limit, order) = (process_id_in.to_string(), from.clone(), to.clone(), *limit, *order);
+        self.blocking(move |store| store.get_messages(&process_id_in, &from, &to, &limit, &order)).await
+    }
+
+    async fn get_message(&self, message_id_in: &str) -> Result<Message, StoreErrorType> {
+        let message_id_in = message_id_in.to_string();
+        self.blocking(move |store| store.get_message(&message_id_in)).await
+    }
+
+    async fn get_message_binary(&self, message_id_in: &str) -> Result<Vec<u8>, StoreErrorType> {
+        let message_id_in = message_id_in.to_string();
+        self.blocking(move |store| store.get_message_binary(&message_id_in)).await
+    }
+
+    async fn get_owner_stats(&self, process_id_in: &str) -> Result<Vec<OwnerStats>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_owner_stats(&process_id_in)).await
+    }
+
+    async fn get_message_ids(&self, process_id_in: &str) -> Result<Vec<String>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_message_ids(&process_id_in)).await
+    }
+
+    async fn get_message_count(&self, process_id_in: &str) -> Result<i64, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_message_count(&process_id_in)).await
+    }
+
+    async fn save_messages(&self, items: &[(Message, Vec<u8>)]) -> Result<String, StoreErrorType> {
+        let items = items.to_vec();
+        self.blocking(move |store| store.save_messages(&items)).await
+    }
+
+    async fn ping(&self) -> Result<(), StoreErrorType> {
+        self.blocking(move |store| store.ping()).await
+    }
+
+    async fn anchor_used(&self, process_id_in: &str, owner_in: &str, anchor_in: &str) -> Result<bool, StoreErrorType> {
+        let (process_id_in, owner_in, anchor_in) = (process_id_in.to_string(), owner_in.to_string(), anchor_in.to_string());
+        self.blocking(move |store| store.anchor_used(&process_id_in, &owner_in, &anchor_in)).await
+    }
+
+    async fn message_ids_unique(&self) -> Result<bool, StoreErrorType> {
+        self.blocking(move |store| store.message_ids_unique()).await
+    }
+
+    async fn find_duplicate_nonces(&self, process_id_in: &str) -> Result<Vec<i32>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.find_duplicate_nonces(&process_id_in)).await
+    }
+
+    async fn get_messages_by_nonce_range(&self, process_id_in: &str, from_nonce: i32, to_nonce: i32) -> Result<Vec<Message>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_messages_by_nonce_range(&process_id_in, from_nonce, to_nonce)).await
+    }
+
+    async fn get_epoch_hash_chains(&self, process_id_in: &str, epoch_in: i32, after_nonce: Option<i32>) -> Result<Vec<(i32, String)>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_epoch_hash_chains(&process_id_in, epoch_in, after_nonce)).await
+    }
+
+    async fn get_epoch_message_counts(&self, process_id_in: &str) -> Result<Vec<(i32, i64)>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_epoch_message_counts(&process_id_in)).await
+    }
+
+    async fn save_epoch_checkpoint(&self, checkpoint: &EpochCheckpoint) -> Result<String, StoreErrorType> {
+        let checkpoint = checkpoint.clone();
+        self.blocking(move |store| store.save_epoch_checkpoint(&checkpoint)).await
+    }
+
+    async fn get_epoch_checkpoint(&self, process_id_in: &str, epoch_in: i32) -> Result<EpochCheckpoint, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_epoch_checkpoint(&process_id_in, epoch_in)).await
+    }
+
+    async fn set_process_frozen(&self, process_id_in: &str, frozen: bool) -> Result<String, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.set_process_frozen(&process_id_in, frozen)).await
+    }
+
+    async fn is_process_frozen(&self, process_id_in: &str) -> Result<bool, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.is_process_frozen(&process_id_in)).await
+    }
+
+    async fn save_pending_upload(&self, item_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        let (item_id_in, bundle_in) = (item_id_in.to_string(), bundle_in.to_vec());
+        self.blocking(move |store| store.save_pending_upload(&item_id_in, &bundle_in)).await
+    }
+
+    async fn get_pending_uploads(&self, after_row_id: i32, max_attempts: i32, limit: i32) -> Result<Vec<PendingUpload>, StoreErrorType> {
+        self.blocking(move |store| store.get_pending_uploads(after_row_id, max_attempts, limit)).await
+    }
+
+    async fn delete_pending_upload(&self, item_id_in: &str) -> Result<String, StoreErrorType> {
+        let item_id_in = item_id_in.to_string();
+        self.blocking(move |store| store.delete_pending_upload(&item_id_in)).await
+    }
+
+    async fn record_upload_failure(&self, item_id_in: &str, error: &str) -> Result<String, StoreErrorType> {
+        let (item_id_in, error) = (item_id_in.to_string(), error.to_string());
+        self.blocking(move |store| store.record_upload_failure(&item_id_in, &error)).await
+    }
+
+    async fn get_pending_upload_statuses(&self, after_row_id: i32, to_row_id: Option<i32>, limit: i32) -> Result<Vec<PendingUploadStatus>, StoreErrorType> {
+        self.blocking(move |store| store.get_pending_upload_statuses(after_row_id, to_row_id, limit)).await
+    }
+
+    async fn get_latest_uploaded_bundle(&self, process_id_in: &str) -> Result<Option<MessageBundle>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_latest_uploaded_bundle(&process_id_in)).await
+    }
+
+    async fn count_pending_uploads(&self, process_id_in: &str) -> Result<i64, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.count_pending_uploads(&process_id_in)).await
+    }
+
+    async fn get_oldest_pending_timestamp(&self, process_id_in: &str) -> Result<Option<i64>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_oldest_pending_timestamp(&process_id_in)).await
+    }
+
+    async fn save_audit_event(&self, event: &AuditEvent) -> Result<String, StoreErrorType> {
+        let event = event.clone();
+        self.blocking(move |store| store.save_audit_event(&event)).await
+    }
+
+    async fn get_message_bundles(&self, from: &Option<String>, to: &Option<String>, after_row_id: i32, limit: i32) -> Result<Vec<MessageBundle>, StoreErrorType> {
+        let (from, to) = (from.clone(), to.clone());
+        self.blocking(move |store| store.get_message_bundles(&from, &to, after_row_id, limit)).await
+    }
+
+    async fn update_message_bundle(&self, message_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType> {
+        let (message_id_in, bundle_in) = (message_id_in.to_string(), bundle_in.to_vec());
+        self.blocking(move |store| store.update_message_bundle(&message_id_in, &bundle_in)).await
+    }
+
+    async fn get_latest_message(&self, process_id_in: &str) -> Result<Option<Message>, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_latest_message(&process_id_in)).await
+    }
+
+    async fn save_process_scheduler(&self, process_scheduler: &ProcessScheduler) -> Result<String, StoreErrorType> {
+        let process_scheduler = process_scheduler.clone();
+        self.blocking(move |store| store.save_process_scheduler(&process_scheduler)).await
+    }
+
+    async fn get_process_scheduler(&self, process_id_in: &str) -> Result<ProcessScheduler, StoreErrorType> {
+        let process_id_in = process_id_in.to_string();
+        self.blocking(move |store| store.get_process_scheduler(&process_id_in)).await
+    }
+
+    async fn save_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType> {
+        let scheduler = scheduler.clone();
+        self.blocking(move |store| store.save_scheduler(&scheduler)).await
+    }
+
+    async fn update_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType> {
+        let scheduler = scheduler.clone();
+        self.blocking(move |store| store.update_scheduler(&scheduler)).await
+    }
+
+    async fn get_scheduler(&self, row_id_in: &i32) -> Result<Scheduler, StoreErrorType> {
+        let row_id_in = *row_id_in;
+        self.blocking(move |store| store.get_scheduler(&row_id_in)).await
+    }
+
+    async fn get_scheduler_by_url(&self, url_in: &str) -> Result<Scheduler, StoreErrorType> {
+        let url_in = url_in.to_string();
+        self.blocking(move |store| store.get_scheduler_by_url(&url_in)).await
+    }
+
+    async fn get_all_schedulers(&self) -> Result<Vec<Scheduler>, StoreErrorType> {
+        self.blocking(move |store| store.get_all_schedulers()).await
+    }
+}
+
 
 #[derive(Queryable, Selectable)]
 #[diesel(table_name = super::schema::processes)]
@@ -451,6 +1327,8 @@ pub struct DbMessage {
     pub timestamp: i64,
     pub bundle: Vec<u8>,
     pub hash_chain: String,
+    pub owner: String,
+    pub compressed: bool,
 }
 
 
@@ -465,6 +1343,8 @@ pub struct NewMessage<'a> {
     pub nonce: &'a i32,
     pub timestamp: &'a i64,
     pub hash_chain: &'a str,
+    pub owner: &'a str,
+    pub compressed: bool,
 }
 
 
@@ -509,4 +1389,304 @@ pub struct DbProcessScheduler {
 pub struct NewProcessScheduler<'a> {
     pub process_id: &'a str,
     pub scheduler_row_id: &'a i32,
-}
\ No newline at end of file
+}
+
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = super::schema::epoch_checkpoints)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbEpochCheckpoint {
+    pub process_id: String,
+    pub epoch: i32,
+    pub root: String,
+    pub first_nonce: i32,
+    pub last_nonce: i32,
+    pub timestamp: i64,
+}
+
+
+#[derive(Insertable)]
+#[diesel(table_name = super::schema::epoch_checkpoints)]
+pub struct NewEpochCheckpoint<'a> {
+    pub process_id: &'a str,
+    pub epoch: &'a i32,
+    pub root: &'a str,
+    pub first_nonce: &'a i32,
+    pub last_nonce: &'a i32,
+    pub timestamp: &'a i64,
+}
+
+
+#[derive(Insertable)]
+#[diesel(table_name = super::schema::frozen_processes)]
+pub struct NewFrozenProcess<'a> {
+    pub process_id: &'a str,
+}
+
+
+#[derive(Insertable)]
+#[diesel(table_name = super::schema::pending_uploads)]
+pub struct NewPendingUpload<'a> {
+    pub item_id: &'a str,
+    pub bundle: &'a [u8],
+}
+
+
+#[derive(Insertable)]
+#[diesel(table_name = super::schema::audit_events)]
+pub struct NewAuditEvent<'a> {
+    pub actor: &'a str,
+    pub action: &'a str,
+    pub parameters: &'a serde_json::Value,
+    pub timestamp: &'a i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressed_binary_round_trip() {
+        let binary: Vec<u8> = b"ao message binary ".repeat(64);
+
+        let stored = encode_binary(&binary, true).expect("compression failed").into_owned();
+        assert!(stored.len() < binary.len());
+        assert_eq!(decode_binary(stored, true).expect("decompression failed"), binary);
+
+        // rows written without compression read back as they are
+        let stored = encode_binary(&binary, false).expect("encoding failed").into_owned();
+        assert_eq!(stored, binary);
+        assert_eq!(decode_binary(stored, false).unwrap(), binary);
+    }
+}
+
+/*
+    run against a real database with
+    TEST_DATABASE_URL=postgres://... cargo test --features postgres-tests
+    every test writes under its own process id so runs can share a database
+*/
+#[cfg(all(test, feature = "postgres-tests"))]
+mod postgres_tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::sync::Arc;
+    use serde_json::json;
+
+    // behind the trait so the tests go through the async wrappers
+    fn test_store() -> Arc<dyn DataStore> {
+        let database_url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set for the postgres tests");
+        let store = StoreClient::connect(&database_url, true).expect("failed to connect");
+        store.run_migrations().expect("failed to run migrations");
+        Arc::new(store)
+    }
+
+    fn unique_id(prefix: &str) -> String {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        format!("{}-{}", prefix, nanos)
+    }
+
+    fn test_process(process_id: &str) -> Process {
+        serde_json::from_value(json!({
+            "process_id": process_id,
+            "block": "000000001000",
+            "owner": { "address": "owner-address", "key": "owner-key" },
+            "tags": [],
+            "timestamp": 1000,
+            "data": null,
+            "anchor": null,
+            "signature": null
+        })).unwrap()
+    }
+
+    fn test_message(process_id: &str, nonce: i32) -> Message {
+        serde_json::from_value(json!({
+            "message": {
+                "id": format!("{}-message-{}", process_id, nonce),
+                "tags": [],
+                "signature": "signature",
+                "anchor": null
+            },
+            "block": "000000001000",
+            "owner": { "address": "owner-address", "key": "owner-key" },
+            "process_id": process_id,
+            "data": null,
+            "epoch": 0,
+            "nonce": nonce,
+            "timestamp": 1000 + nonce as i64,
+            "hash_chain": "hash-chain"
+        })).unwrap()
+    }
+
+    // a process with count messages at timestamps 1000, 1001, ...
+    async fn store_with_messages(store: &Arc<dyn DataStore>, count: i32) -> String {
+        let process_id = unique_id("process");
+        store.save_process(&test_process(&process_id), b"process bundle").await.unwrap();
+        for nonce in 0..count {
+            let bundle = format!("message bundle {}", nonce).into_bytes();
+            store.save_message(&test_message(&process_id, nonce), &bundle).await.unwrap();
+        }
+        process_id
+    }
+
+    fn nonces(paginated: &PaginatedMessages) -> Vec<i32> {
+        paginated.edges.iter().map(|edge| edge.node.nonce).collect()
+    }
+
+    #[tokio::test]
+    async fn test_process_and_message_round_trip() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 1).await;
+
+        let process = store.get_process(&process_id).await.unwrap();
+        assert_eq!(process.process_id, process_id);
+
+        let message_id = format!("{}-message-0", process_id);
+        let message = store.get_message(&message_id).await.unwrap();
+        assert_eq!(message.nonce, 0);
+        assert_eq!(store.get_message_binary(&message_id).await.unwrap(), b"message bundle 0");
+
+        assert!(matches!(
+            store.get_message(&unique_id("missing")).await,
+            Err(StoreErrorType::NotFound(_))
+        ));
+
+        store.delete_message(&message_id).await.unwrap();
+        assert!(matches!(store.get_message(&message_id).await, Err(StoreErrorType::NotFound(_))));
+        store.delete_process(&process_id).await.unwrap();
+        assert!(matches!(store.get_process(&process_id).await, Err(StoreErrorType::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_range() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 6).await;
+
+        let all = store.get_messages(&process_id, &None, &None, &None, &None).await.unwrap();
+        assert_eq!(nonces(&all), vec![0, 1, 2, 3, 4, 5]);
+        assert!(!all.page_info.has_next_page);
+        assert_eq!(store.get_message_count(&process_id).await.unwrap(), 6);
+
+        // from is exclusive and to is inclusive
+        let from = Some("1001".to_string());
+        let to = Some("1004".to_string());
+        let range = store.get_messages(&process_id, &from, &to, &None, &None).await.unwrap();
+        assert_eq!(nonces(&range), vec![2, 3, 4]);
+
+        let page = store.get_messages(&process_id, &from, &None, &Some(2), &None).await.unwrap();
+        assert_eq!(nonces(&page), vec![2, 3]);
+        assert!(page.page_info.has_next_page);
+
+        let next = store.get_messages(&process_id, &page.page_info.next_cursor, &None, &Some(2), &None).await.unwrap();
+        assert_eq!(nonces(&next), vec![4, 5]);
+        assert!(!next.page_info.has_next_page);
+
+        let descending = store.get_messages(&process_id, &Some("1004".to_string()), &None, &Some(2), &Some(SortOrder::Desc)).await.unwrap();
+        assert_eq!(nonces(&descending), vec![3, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_batch_cursor() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 0).await;
+        // the messages of a batch share the timestamp of its bundle
+        let batch: Vec<(Message, Vec<u8>)> = (0..5)
+            .map(|nonce| {
+                let mut message = test_message(&process_id, nonce);
+                message.timestamp = 1000;
+                (message, vec![])
+            })
+            .collect();
+        store.save_messages(&batch).await.unwrap();
+
+        for order in [None, Some(SortOrder::Desc)] {
+            let mut from = None;
+            let mut found = vec![];
+            loop {
+                let page = store.get_messages(&process_id, &from, &None, &Some(2), &order).await.unwrap();
+                found.extend(nonces(&page));
+                match page.page_info.next_cursor {
+                    Some(cursor) => from = Some(cursor),
+                    None => break
+                }
+            }
+            if order.is_some() {
+                found.reverse();
+            }
+            assert_eq!(found, vec![0, 1, 2, 3, 4]);
+        }
+
+        // a bare timestamp still starts after the whole batch
+        let after = store.get_messages(&process_id, &Some("1000".to_string()), &None, &None, &None).await.unwrap();
+        assert!(after.edges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_batch_counts_every_message() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 1).await;
+        // a batch shares one bundle, its upload is tracked under the first id
+        let bundle = unique_id("batch bundle").into_bytes();
+        let batch: Vec<(Message, Vec<u8>)> = (1..4)
+            .map(|nonce| (test_message(&process_id, nonce), bundle.clone()))
+            .collect();
+        store.save_messages(&batch).await.unwrap();
+        let first_id = batch[0].0.message.id.clone();
+        store.save_pending_upload(&first_id, &bundle).await.unwrap();
+
+        assert_eq!(store.count_pending_uploads(&process_id).await.unwrap(), 3);
+        assert_eq!(store.get_oldest_pending_timestamp(&process_id).await.unwrap(), Some(1001));
+        let latest = store.get_latest_uploaded_bundle(&process_id).await.unwrap().unwrap();
+        assert_eq!(latest.message_id, format!("{}-message-0", process_id));
+
+        store.delete_pending_upload(&first_id).await.unwrap();
+        assert_eq!(store.count_pending_uploads(&process_id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_message_ids_unique() {
+        let store = test_store();
+        assert!(store.message_ids_unique().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_by_nonce_range() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 5).await;
+
+        let messages = store.get_messages_by_nonce_range(&process_id, 1, 3).await.unwrap();
+        let found: Vec<i32> = messages.iter().map(|message| message.nonce).collect();
+        assert_eq!(found, vec![1, 2, 3]);
+
+        let hash_chains = store.get_epoch_hash_chains(&process_id, 0, Some(2)).await.unwrap();
+        let found: Vec<i32> = hash_chains.iter().map(|(nonce, _)| *nonce).collect();
+        assert_eq!(found, vec![3, 4]);
+        assert_eq!(store.get_epoch_hash_chains(&process_id, 0, None).await.unwrap().len(), 5);
+
+        let latest = store.get_latest_message(&process_id).await.unwrap();
+        assert_eq!(latest.map(|message| message.nonce), Some(4));
+        assert!(store.get_latest_message(&unique_id("empty")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_save_messages_is_atomic() {
+        let store = test_store();
+        let process_id = store_with_messages(&store, 2).await;
+
+        let batch: Vec<(Message, Vec<u8>)> = (2..5)
+            .map(|nonce| (test_message(&process_id, nonce), format!("message bundle {}", nonce).into_bytes()))
+            .collect();
+        store.save_messages(&batch).await.unwrap();
+        assert_eq!(store.get_message_count(&process_id).await.unwrap(), 5);
+
+        // the last item repeats a stored id, so none of the batch is kept
+        let mut batch: Vec<(Message, Vec<u8>)> = (5..7)
+            .map(|nonce| (test_message(&process_id, nonce), vec![]))
+            .collect();
+        batch.push((test_message(&process_id, 4), vec![]));
+        assert!(store.save_messages(&batch).await.is_err());
+        assert_eq!(store.get_message_count(&process_id).await.unwrap(), 5);
+        let latest = store.get_latest_message(&process_id).await.unwrap();
+        assert_eq!(latest.map(|message| message.nonce), Some(4));
+    }
+}
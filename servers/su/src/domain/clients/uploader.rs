@@ -1,7 +1,9 @@
 
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use async_trait::async_trait;
 use reqwest::{Url, Client};
 
 extern crate serde;
@@ -13,9 +15,124 @@ use tokio::time::{sleep, Duration};
 use crate::domain::Log;
 use crate::domain::core::dal::{Uploader, UploaderErrorType};
 
+// size of each chunk when a binary is uploaded in chunks
+const CHUNK_SIZE: usize = 5 * 1024 * 1024;
+const CHUNK_ATTEMPTS: u32 = 10;
+const CHUNK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 pub struct UploaderClient {
     node_url: Url,
     logger: Arc<dyn Log>,
+    chunk_threshold: usize,
+    pending: Arc<AtomicUsize>,
+}
+
+// held by a spawned upload, counts it as pending until dropped
+struct Pending(Arc<AtomicUsize>);
+
+impl Pending {
+    fn start(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Pending(Arc::clone(count))
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkSession {
+    id: String,
+}
+
+/*
+    the begin/upload-chunk/finalize protocol of a
+    chunked upload, split out from the http client
+    so the chunking itself can be driven by a mock
+*/
+#[async_trait]
+trait ChunkBackend: Send + Sync {
+    async fn begin(&self) -> Result<String, UploaderErrorType>;
+    async fn upload_chunk(&self, upload_id: &str, offset: usize, chunk: Vec<u8>) -> Result<(), UploaderErrorType>;
+    async fn finalize(&self, upload_id: &str) -> Result<(), UploaderErrorType>;
+}
+
+struct NodeChunks {
+    node_url: Url,
+    client: Client,
+}
+
+impl NodeChunks {
+    async fn post(&self, path: String, body: Vec<u8>) -> Result<reqwest::Response, UploaderErrorType> {
+        let url = self.node_url
+            .join(&path)
+            .map_err(|e| UploaderErrorType::UploadError(format!("{}", e)))?;
+        let response = self.client
+            .post(url)
+            .header("Content-Type", "application/octet-stream")
+            .body(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(UploaderErrorType::UploadError(format!("Non-success status: {}", response.status())));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ChunkBackend for NodeChunks {
+    async fn begin(&self) -> Result<String, UploaderErrorType> {
+        let response = self.post("chunks/arweave/-1/-1".to_string(), vec![]).await?;
+        let session: ChunkSession = serde_json::from_str(&response.text().await?)?;
+        Ok(session.id)
+    }
+
+    async fn upload_chunk(&self, upload_id: &str, offset: usize, chunk: Vec<u8>) -> Result<(), UploaderErrorType> {
+        self.post(format!("chunks/arweave/{}/{}", upload_id, offset), chunk).await?;
+        Ok(())
+    }
+
+    async fn finalize(&self, upload_id: &str) -> Result<(), UploaderErrorType> {
+        self.post(format!("chunks/arweave/{}/-1", upload_id), vec![]).await?;
+        Ok(())
+    }
+}
+
+/*
+    upload tx chunk by chunk, a failed chunk is retried
+    on its own so a network blip resumes from that
+    offset instead of restarting the whole upload
+*/
+async fn upload_chunked(
+    backend: &dyn ChunkBackend,
+    logger: &Arc<dyn Log>,
+    tx: &[u8],
+    chunk_size: usize,
+    retry_delay: Duration
+) -> Result<(), UploaderErrorType> {
+    let upload_id = backend.begin().await?;
+
+    for (index, chunk) in tx.chunks(chunk_size).enumerate() {
+        let offset = index * chunk_size;
+        let mut attempt = 1;
+        loop {
+            match backend.upload_chunk(&upload_id, offset, chunk.to_vec()).await {
+                Ok(()) => break,
+                Err(e) if attempt < CHUNK_ATTEMPTS => {
+                    logger.error(format!("Chunk at offset {} failed, attempt {}: {:?}", offset, attempt, e));
+                    attempt += 1;
+                    sleep(retry_delay).await;
+                }
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
+    backend.finalize(&upload_id).await
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -41,7 +158,7 @@ impl From<serde_json::Error> for UploaderErrorType {
 }
 
 impl UploaderClient {
-    pub fn new(node_url: &str, logger: Arc<dyn Log>) -> Result<Self, UploaderErrorType> {
+    pub fn new(node_url: &str, logger: Arc<dyn Log>, chunk_threshold: usize) -> Result<Self, UploaderErrorType> {
         let url = match Url::parse(node_url) {
             Ok(u) => u,
             Err(e) => return Err(UploaderErrorType::UploadError(format!("{}", e)))
@@ -49,18 +166,42 @@ impl UploaderClient {
 
         Ok(UploaderClient {
             node_url: url,
-            logger
+            logger,
+            chunk_threshold,
+            pending: Arc::new(AtomicUsize::new(0))
         })
     }
 }
 
+#[async_trait]
 impl Uploader for UploaderClient {
-    fn upload(&self, tx: Vec<u8>) -> Result<(), UploaderErrorType> {
+    async fn upload(&self, tx: Vec<u8>) -> Result<(), UploaderErrorType> {
+        if tx.len() > self.chunk_threshold {
+            let backend = NodeChunks {
+                node_url: self.node_url.clone(),
+                client: Client::new()
+            };
+            let logger_clone = Arc::clone(&self.logger);
+            let pending = Pending::start(&self.pending);
+
+            spawn(async move {
+                let _pending = pending;
+                match upload_chunked(&backend, &logger_clone, &tx, CHUNK_SIZE, CHUNK_RETRY_DELAY).await {
+                    Ok(()) => logger_clone.log("Chunked upload successful".to_string()),
+                    Err(e) => logger_clone.error(format!("Chunked upload failed: {:?}", e))
+                }
+            });
+
+            return Ok(());
+        }
+
         let node_url_clone = self.node_url.clone();
         let tx_clone = tx.clone();
         let logger_clone = Arc::clone(&self.logger);
+        let pending = Pending::start(&self.pending);
 
         spawn(async move {
+            let _pending = pending;
             let client = Client::new();
 
             for _attempt in 0..100 {
@@ -97,4 +238,92 @@ impl Uploader for UploaderClient {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn backlog(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    async fn price(&self, byte_len: u64) -> Result<u64, UploaderErrorType> {
+        let url = self.node_url
+            .join(&format!("price/arweave/{}", byte_len))
+            .map_err(|e| UploaderErrorType::UploadError(format!("{}", e)))?;
+
+        let response = Client::new().get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(UploaderErrorType::UploadError(format!("Non-success status: {}", response.status())));
+        }
+
+        let body = response.text().await?;
+        body.trim().parse::<u64>()
+            .map_err(|e| UploaderErrorType::UploadError(format!("Invalid price {}: {}", body, e)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    struct MockLogger;
+    impl Log for MockLogger {
+        fn log(&self, message: String) {
+            println!("{}", message)
+        }
+        fn error(&self, message: String) {
+            println!("{}", message);
+        }
+    }
+
+    /*
+        stores chunks by offset and assembles them on
+        finalize, the first attempt at every other
+        chunk fails to exercise the per chunk retry
+    */
+    #[derive(Default)]
+    struct MockChunks {
+        chunks: Mutex<BTreeMap<usize, Vec<u8>>>,
+        failed: Mutex<Vec<usize>>,
+        assembled: Mutex<Vec<u8>>,
+    }
+
+    #[async_trait]
+    impl ChunkBackend for MockChunks {
+        async fn begin(&self) -> Result<String, UploaderErrorType> {
+            Ok("upload-id".to_string())
+        }
+
+        async fn upload_chunk(&self, upload_id: &str, offset: usize, chunk: Vec<u8>) -> Result<(), UploaderErrorType> {
+            assert_eq!(upload_id, "upload-id");
+            let mut failed = self.failed.lock().unwrap();
+            if (offset / 1024) % 2 == 1 && !failed.contains(&offset) {
+                failed.push(offset);
+                return Err(UploaderErrorType::UploadError("network blip".to_string()));
+            }
+            self.chunks.lock().unwrap().insert(offset, chunk);
+            Ok(())
+        }
+
+        async fn finalize(&self, _upload_id: &str) -> Result<(), UploaderErrorType> {
+            let assembled = self.chunks.lock().unwrap().values().flatten().cloned().collect();
+            *self.assembled.lock().unwrap() = assembled;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_chunked() {
+        let backend = MockChunks::default();
+        let logger: Arc<dyn Log> = Arc::new(MockLogger);
+        let tx: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+
+        upload_chunked(&backend, &logger, &tx, 1024, Duration::ZERO)
+            .await
+            .expect("chunked upload failed");
+
+        assert_eq!(backend.chunks.lock().unwrap().len(), 10);
+        assert_eq!(backend.failed.lock().unwrap().len(), 5);
+        assert_eq!(*backend.assembled.lock().unwrap(), tx);
+    }
+}
@@ -1,8 +1,10 @@
 use std::env;
+use std::str::FromStr;
 
 use dotenv::dotenv;
 
 use crate::domain::Config;
+use crate::domain::core::dal::{TagOrderMode, VerificationMode, ShapeCheckMode, ReadTransform, ExpiredMessageMode, ProtocolType, ConfigValue, config_values};
 
 #[derive(Debug)]
 pub struct AoConfig {
@@ -11,23 +13,155 @@ pub struct AoConfig {
     pub gateway_url: String,
     pub upload_node_url: String,
     pub mode: String,
-    pub scheduler_list_path: String
+    pub scheduler_list_path: String,
+    pub enable_su_version_tag: bool,
+    pub bundle_version: String,
+    pub tag_order_mode: TagOrderMode,
+    pub canonical_tag_order: Vec<String>,
+    pub upload_chunk_threshold: usize,
+    pub outer_target_process: bool,
+    pub enforce_memory_limit: bool,
+    pub check_head_ttl_seconds: u64,
+    pub check_head_negative_ttl_seconds: u64,
+    pub verification_cache_ttl_seconds: u64,
+    pub network_info_ttl_seconds: u64,
+    pub max_tag_count: usize,
+    pub max_tag_bytes: usize,
+    pub verification_mode: VerificationMode,
+    pub shape_check_mode: ShapeCheckMode,
+    pub shape_check_min_tags: usize,
+    pub shape_check_min_bytes_per_tag: usize,
+    pub allowed_variants: Vec<String>,
+    pub allowed_signature_types: Vec<u16>,
+    pub allowed_data_protocols: Vec<String>,
+    pub protocol_type_matrix: Vec<ProtocolType>,
+    pub allowed_gateways: Vec<String>,
+    pub reject_unknown_gateway_hints: bool,
+    pub trusted_bundle_signers: Vec<String>,
+    pub reject_reused_anchors: bool,
+    pub write_latency_slo_ms: u64,
+    pub message_subscription_buffer: usize,
+    pub epoch_length: i32,
+    pub hash_chain_seed_domain: String,
+    pub read_transforms: Vec<ReadTransform>,
+    pub verbose_log_sample_rate: usize,
+    pub idempotent_process_creation: bool,
+    pub timestamp_window_seconds: i64,
+    pub compress_binaries: bool,
+    pub verify_stored_binaries: bool,
+    pub expired_message_mode: ExpiredMessageMode,
+    pub self_test_on_startup: bool,
+    pub audit_admin_actions: bool,
+    pub admin_token: String,
+    pub max_response_bytes: usize,
+    pub defer_uploads: bool,
+    pub max_concurrent_process_creations: usize,
+    pub max_in_flight_writes: usize,
+    pub max_upload_backlog: usize,
+    pub load_level_in_responses: bool
+}
+
+fn required(name: &str) -> Result<String, String> {
+    env::var(name).map_err(|e| format!("{}: {}", name, e))
+}
+
+/*
+    optional settings fall back to their default
+    when unset but fail loudly on an unparseable value
+*/
+fn optional<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match env::var(name) {
+        Ok(value) => value.parse::<T>()
+            .map_err(|_| format!("{}: invalid value {}", name, value)),
+        Err(env::VarError::NotPresent) => Ok(default),
+        Err(e) => Err(format!("{}: {}", name, e))
+    }
+}
+
+// an optional number that must be at least min
+fn optional_at_least<T: FromStr + PartialOrd + std::fmt::Display>(name: &str, default: T, min: T) -> Result<T, String> {
+    let value = optional(name, default)?;
+    if value < min {
+        return Err(format!("{}: must be at least {}, got {}", name, min, value));
+    }
+    Ok(value)
+}
+
+// a comma separated list, empty when unset
+fn optional_list<T: FromStr>(name: &str) -> Result<Vec<T>, String> {
+    optional_list_or(name, "")
+}
+
+// a comma separated list, parsed from default when unset
+fn optional_list_or<T: FromStr>(name: &str, default: &str) -> Result<Vec<T>, String> {
+    optional(name, default.to_string())?
+        .split(',')
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<T>()
+            .map_err(|_| format!("{}: invalid value {}", name, value)))
+        .collect()
 }
 
 impl AoConfig {
-    pub fn new(mode: Option<String>) -> Result<Self, env::VarError> {
+    pub fn new(mode: Option<String>) -> Result<Self, String> {
         dotenv().ok();
         let mode_out = match mode {
             Some(m) => m,
-            None => env::var("MODE")?
+            None => required("MODE")?
         };
         Ok(AoConfig {
-            database_url: env::var("DATABASE_URL")?,
-            su_wallet_path: env::var("SU_WALLET_PATH")?,
-            gateway_url: env::var("GATEWAY_URL")?,
-            upload_node_url: env::var("UPLOAD_NODE_URL")?,
+            database_url: required("DATABASE_URL")?,
+            su_wallet_path: required("SU_WALLET_PATH")?,
+            gateway_url: required("GATEWAY_URL")?,
+            upload_node_url: required("UPLOAD_NODE_URL")?,
             mode: mode_out,
-            scheduler_list_path: env::var("SCHEDULER_LIST_PATH")?,
+            scheduler_list_path: required("SCHEDULER_LIST_PATH")?,
+            enable_su_version_tag: optional("ENABLE_SU_VERSION_TAG", true)?,
+            bundle_version: optional("BUNDLE_VERSION", "2.0.0".to_string())?,
+            tag_order_mode: optional("TAG_ORDER_MODE", TagOrderMode::None)?,
+            canonical_tag_order: optional_list("CANONICAL_TAG_ORDER")?,
+            upload_chunk_threshold: optional("UPLOAD_CHUNK_THRESHOLD", 10 * 1024 * 1024)?,
+            outer_target_process: optional("OUTER_TARGET_PROCESS", false)?,
+            enforce_memory_limit: optional("ENFORCE_MEMORY_LIMIT", false)?,
+            check_head_ttl_seconds: optional("CHECK_HEAD_TTL_SECONDS", 60)?,
+            check_head_negative_ttl_seconds: optional("CHECK_HEAD_NEGATIVE_TTL_SECONDS", 5)?,
+            verification_cache_ttl_seconds: optional("VERIFICATION_CACHE_TTL_SECONDS", 30)?,
+            network_info_ttl_seconds: optional("NETWORK_INFO_TTL_SECONDS", 30)?,
+            max_tag_count: optional("MAX_TAG_COUNT", 128)?,
+            max_tag_bytes: optional("MAX_TAG_BYTES", 3072)?,
+            verification_mode: optional("VERIFICATION_MODE", VerificationMode::Strict)?,
+            shape_check_mode: optional("SHAPE_CHECK_MODE", ShapeCheckMode::Off)?,
+            shape_check_min_tags: optional("SHAPE_CHECK_MIN_TAGS", 32)?,
+            shape_check_min_bytes_per_tag: optional("SHAPE_CHECK_MIN_BYTES_PER_TAG", 16)?,
+            allowed_variants: optional_list("ALLOWED_VARIANTS")?,
+            allowed_signature_types: optional_list("ALLOWED_SIGNATURE_TYPES")?,
+            allowed_data_protocols: optional_list_or("ALLOWED_DATA_PROTOCOLS", "ao")?,
+            protocol_type_matrix: optional_list_or("PROTOCOL_TYPE_MATRIX", "ao:Process")?,
+            allowed_gateways: optional_list("ALLOWED_GATEWAYS")?,
+            reject_unknown_gateway_hints: optional("REJECT_UNKNOWN_GATEWAY_HINTS", false)?,
+            trusted_bundle_signers: optional_list("TRUSTED_BUNDLE_SIGNERS")?,
+            reject_reused_anchors: optional("REJECT_REUSED_ANCHORS", false)?,
+            write_latency_slo_ms: optional("WRITE_LATENCY_SLO_MS", 0)?,
+            message_subscription_buffer: optional_at_least("MESSAGE_SUBSCRIPTION_BUFFER", 1024, 1)?,
+            epoch_length: optional("EPOCH_LENGTH", 0)?,
+            hash_chain_seed_domain: optional("HASH_CHAIN_SEED_DOMAIN", "".to_string())?,
+            read_transforms: optional_list("READ_TRANSFORMS")?,
+            verbose_log_sample_rate: optional("VERBOSE_LOG_SAMPLE_RATE", 1)?,
+            idempotent_process_creation: optional("IDEMPOTENT_PROCESS_CREATION", false)?,
+            timestamp_window_seconds: optional("TIMESTAMP_WINDOW_SECONDS", 3600)?,
+            compress_binaries: optional("COMPRESS_BINARIES", false)?,
+            verify_stored_binaries: optional("VERIFY_STORED_BINARIES", false)?,
+            expired_message_mode: optional("EXPIRED_MESSAGE_MODE", ExpiredMessageMode::Mark)?,
+            self_test_on_startup: optional("SELF_TEST_ON_STARTUP", false)?,
+            audit_admin_actions: optional("AUDIT_ADMIN_ACTIONS", false)?,
+            admin_token: optional("ADMIN_TOKEN", "".to_string())?,
+            max_response_bytes: optional("MAX_RESPONSE_BYTES", 0)?,
+            defer_uploads: optional("DEFER_UPLOADS", false)?,
+            max_concurrent_process_creations: optional("MAX_CONCURRENT_PROCESS_CREATIONS", 10)?,
+            max_in_flight_writes: optional("MAX_IN_FLIGHT_WRITES", 100)?,
+            max_upload_backlog: optional("MAX_UPLOAD_BACKLOG", 1000)?,
+            load_level_in_responses: optional("LOAD_LEVEL_IN_RESPONSES", false)?,
         })
     }
 }
@@ -48,4 +182,144 @@ impl Config for AoConfig {
     fn scheduler_list_path(&self) -> String {
         self.scheduler_list_path.clone()
     }
-}
\ No newline at end of file
+    fn enable_su_version_tag(&self) -> bool {
+        self.enable_su_version_tag
+    }
+    fn bundle_version(&self) -> String {
+        self.bundle_version.clone()
+    }
+    fn tag_order_mode(&self) -> TagOrderMode {
+        self.tag_order_mode
+    }
+    fn canonical_tag_order(&self) -> Vec<String> {
+        self.canonical_tag_order.clone()
+    }
+    fn upload_chunk_threshold(&self) -> usize {
+        self.upload_chunk_threshold
+    }
+    fn outer_target_process(&self) -> bool {
+        self.outer_target_process
+    }
+    fn enforce_memory_limit(&self) -> bool {
+        self.enforce_memory_limit
+    }
+    fn check_head_ttl_seconds(&self) -> u64 {
+        self.check_head_ttl_seconds
+    }
+    fn check_head_negative_ttl_seconds(&self) -> u64 {
+        self.check_head_negative_ttl_seconds
+    }
+    fn verification_cache_ttl_seconds(&self) -> u64 {
+        self.verification_cache_ttl_seconds
+    }
+    fn network_info_ttl_seconds(&self) -> u64 {
+        self.network_info_ttl_seconds
+    }
+    fn max_tag_count(&self) -> usize {
+        self.max_tag_count
+    }
+    fn max_tag_bytes(&self) -> usize {
+        self.max_tag_bytes
+    }
+    fn verification_mode(&self) -> VerificationMode {
+        self.verification_mode
+    }
+    fn shape_check_mode(&self) -> ShapeCheckMode {
+        self.shape_check_mode
+    }
+    fn shape_check_min_tags(&self) -> usize {
+        self.shape_check_min_tags
+    }
+    fn shape_check_min_bytes_per_tag(&self) -> usize {
+        self.shape_check_min_bytes_per_tag
+    }
+    fn allowed_variants(&self) -> Vec<String> {
+        self.allowed_variants.clone()
+    }
+    fn allowed_signature_types(&self) -> Vec<u16> {
+        self.allowed_signature_types.clone()
+    }
+    fn allowed_data_protocols(&self) -> Vec<String> {
+        self.allowed_data_protocols.clone()
+    }
+    fn protocol_type_matrix(&self) -> Vec<ProtocolType> {
+        self.protocol_type_matrix.clone()
+    }
+    fn allowed_gateways(&self) -> Vec<String> {
+        self.allowed_gateways.clone()
+    }
+    fn reject_unknown_gateway_hints(&self) -> bool {
+        self.reject_unknown_gateway_hints
+    }
+    fn trusted_bundle_signers(&self) -> Vec<String> {
+        self.trusted_bundle_signers.clone()
+    }
+    fn reject_reused_anchors(&self) -> bool {
+        self.reject_reused_anchors
+    }
+    fn write_latency_slo_ms(&self) -> u64 {
+        self.write_latency_slo_ms
+    }
+    fn message_subscription_buffer(&self) -> usize {
+        self.message_subscription_buffer
+    }
+    fn epoch_length(&self) -> i32 {
+        self.epoch_length
+    }
+    fn hash_chain_seed_domain(&self) -> String {
+        self.hash_chain_seed_domain.clone()
+    }
+    fn read_transforms(&self) -> Vec<ReadTransform> {
+        self.read_transforms.clone()
+    }
+    fn verbose_log_sample_rate(&self) -> usize {
+        self.verbose_log_sample_rate
+    }
+    fn idempotent_process_creation(&self) -> bool {
+        self.idempotent_process_creation
+    }
+    fn timestamp_window_seconds(&self) -> i64 {
+        self.timestamp_window_seconds
+    }
+    fn compress_binaries(&self) -> bool {
+        self.compress_binaries
+    }
+    fn verify_stored_binaries(&self) -> bool {
+        self.verify_stored_binaries
+    }
+    fn expired_message_mode(&self) -> ExpiredMessageMode {
+        self.expired_message_mode
+    }
+    fn self_test_on_startup(&self) -> bool {
+        self.self_test_on_startup
+    }
+    fn audit_admin_actions(&self) -> bool {
+        self.audit_admin_actions
+    }
+    fn admin_token(&self) -> String {
+        self.admin_token.clone()
+    }
+    fn max_response_bytes(&self) -> usize {
+        self.max_response_bytes
+    }
+    fn defer_uploads(&self) -> bool {
+        self.defer_uploads
+    }
+    fn max_concurrent_process_creations(&self) -> usize {
+        self.max_concurrent_process_creations
+    }
+    fn max_in_flight_writes(&self) -> usize {
+        self.max_in_flight_writes
+    }
+    fn max_upload_backlog(&self) -> usize {
+        self.max_upload_backlog
+    }
+    fn load_level_in_responses(&self) -> bool {
+        self.load_level_in_responses
+    }
+    fn values(&self) -> Vec<ConfigValue> {
+        let mut values = config_values(self);
+        values.push(ConfigValue::secret("DATABASE_URL", &self.database_url));
+        values
+    }
+}
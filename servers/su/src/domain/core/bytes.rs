@@ -5,6 +5,7 @@ use bytes::{BufMut, Bytes};
 use bundlr_sdk::{error::BundlrError, tags::*};
 
 use sha2::{Digest, Sha256, Sha384};
+use sha3::Keccak256;
 use base64_url;
 
 use ring::rand::SecureRandom;
@@ -20,6 +21,12 @@ impl From<BundlrError> for ByteErrorType {
     }
 }
 
+impl From<ByteErrorType> for String {
+    fn from(error: ByteErrorType) -> Self {
+        format!("{:?}", error)
+    }
+}
+
 impl From<&str> for ByteErrorType {
     fn from(error: &str) -> Self {
         ByteErrorType::ByteError(format!("Byte error: {}", error.to_string()))
@@ -63,6 +70,77 @@ impl DataBundle {
 
         Ok(buffer)
     }
+
+    /*
+        parse a bundle from the bytes of the data item
+        wrapping it, the wrapper tags become the bundle tags
+    */
+    pub fn from_bytes(buffer: Vec<u8>) -> Result<Self, ByteErrorType> {
+        let wrapper = DataItem::from_bytes(buffer)?;
+        let data = wrapper.data_bytes();
+
+        if data.len() < 32 {
+            return Err(ByteErrorType::ByteError("Buffer too short for item count".to_string()));
+        }
+        let item_count = byte_array_to_long(&data[0..32])? as usize;
+
+        let headers_end = item_count
+            .checked_mul(64)
+            .and_then(|len| len.checked_add(32))
+            .filter(|end| *end <= data.len())
+            .ok_or(ByteErrorType::ByteError("Buffer too short for item headers".to_string()))?;
+
+        let mut items = Vec::with_capacity(item_count);
+        let mut offset = headers_end;
+        for index in 0..item_count {
+            let header = &data[32 + 64 * index..32 + 64 * (index + 1)];
+            let size = byte_array_to_long(&header[0..32])? as usize;
+            let end = offset
+                .checked_add(size)
+                .filter(|end| *end <= data.len())
+                .ok_or(ByteErrorType::ByteError(format!("Buffer too short for item {}", index)))?;
+
+            let item = DataItem::from_bytes(data[offset..end].to_vec())?;
+            if item.raw_id() != header[32..64] {
+                return Err(ByteErrorType::ByteError(format!("Item {} id does not match its header", index)));
+            }
+            items.push(item);
+            offset = end;
+        }
+
+        Ok(DataBundle { items, tags: wrapper.tags() })
+    }
+
+    // guards against serialization dropping or duplicating items
+    pub fn verify_item_count(&self, expected: usize) -> Result<(), ByteErrorType> {
+        if self.items.len() != expected {
+            return Err(ByteErrorType::ByteError(format!(
+                "Bundle contains {} items, expected {}", self.items.len(), expected
+            )));
+        }
+        Ok(())
+    }
+}
+
+// encode tags as they are written into a data item and decode them back
+pub fn round_trip_tags(tags: &[Tag]) -> Result<Vec<Tag>, ByteErrorType> {
+    if tags.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut encoded = tags.to_vec().encode()?.to_vec();
+    let mut encoded_slice = &mut encoded[..];
+    Ok(encoded_slice.decode()?)
+}
+
+fn byte_array_to_long(bytes: &[u8]) -> Result<u64, ByteErrorType> {
+    if bytes[8..].iter().any(|b| *b != 0) {
+        return Err(ByteErrorType::ByteError("Length too large".to_string()));
+    }
+    let mut value: u64 = 0;
+    for byte in bytes[0..8].iter().rev() {
+        value = (value << 8) | *byte as u64;
+    }
+    Ok(value)
 }
 
 fn long_to_n_byte_array(n: usize, long: u64) -> Result<Vec<u8>, ByteErrorType> {
@@ -110,15 +188,31 @@ pub struct Config {
 pub enum SignerMap {
     None = -1,
     Arweave = 1,
+    // solana and other ed25519 keys
+    Ed25519 = 2,
+    // secp256k1, the owner is the uncompressed public key
+    Ethereum = 3,
 }
 
 
 impl SignerMap {
     pub fn get_config(&self) -> Config {
-        Config {
-            sig_length: 512,
-            pub_length: 512,
-            sig_name: "arweave".to_owned(),
+        match self {
+            SignerMap::Ed25519 => Config {
+                sig_length: 64,
+                pub_length: 32,
+                sig_name: "ed25519".to_owned(),
+            },
+            SignerMap::Ethereum => Config {
+                sig_length: 65,
+                pub_length: 65,
+                sig_name: "ethereum".to_owned(),
+            },
+            _ => Config {
+                sig_length: 512,
+                pub_length: 512,
+                sig_name: "arweave".to_owned(),
+            }
         }
     }
 }
@@ -127,6 +221,8 @@ impl SignerMap {
     pub fn as_u16(&self) -> u16 {
         match self {
             SignerMap::Arweave => 1,
+            SignerMap::Ed25519 => 2,
+            SignerMap::Ethereum => 3,
             _ => u16::MAX,
         }
     }
@@ -136,11 +232,31 @@ impl From<u16> for SignerMap {
     fn from(t: u16) -> Self {
         match t {
             1 => SignerMap::Arweave,
+            2 => SignerMap::Ed25519,
+            3 => SignerMap::Ethereum,
             _ => SignerMap::None,
         }
     }
 }
 
+/*
+    the eip-55 checksummed address of an uncompressed
+    secp256k1 public key, the last 20 bytes of the
+    keccak256 of the key without its 0x04 prefix
+*/
+fn ethereum_address(public_key: &[u8]) -> String {
+    let key = public_key.strip_prefix(&[4]).unwrap_or(public_key);
+    let address = hex::encode(&Keccak256::digest(key)[12..]);
+    let checksum = Keccak256::digest(address.as_bytes());
+    let checksummed: String = address.chars().enumerate()
+        .map(|(i, c)| {
+            let nibble = if i % 2 == 0 { checksum[i / 2] >> 4 } else { checksum[i / 2] & 0x0f };
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
 pub const LIST_AS_BUFFER: &[u8] = "list".as_bytes();
 pub const BLOB_AS_BUFFER: &[u8] = "blob".as_bytes();
 pub const DATAITEM_AS_BUFFER: &[u8] = "dataitem".as_bytes();
@@ -391,6 +507,28 @@ impl DataItem {
         Ok(b)
     }
 
+    // the length as_bytes gives once signed, the item does not need to be signed yet
+    pub fn serialized_len(&self) -> Result<usize, ByteErrorType> {
+        let data_len = match &self.data {
+            Data::None => return Err(ByteErrorType::ByteError("invalid data type".to_string())),
+            Data::Bytes(data) => data.len(),
+        };
+        let encoded_tags_len = if !self.tags.is_empty() {
+            self.tags.encode()?.len()
+        } else {
+            0
+        };
+        let config = self.signature_type.get_config();
+        Ok(2
+            + config.sig_length
+            + config.pub_length
+            + 1 + self.target.len()
+            + 1 + self.anchor.len()
+            + 16
+            + encoded_tags_len
+            + data_len)
+    }
+
     pub fn raw_id(&self) -> Vec<u8> {
         let mut hasher = Sha256::new();
         hasher.update(&self.signature);
@@ -407,6 +545,18 @@ impl DataItem {
         owner_base64
     }
 
+    /*
+        the address of the owner, an ethereum owner has
+        its checksummed 0x address and any other owner
+        the base64url sha256 of its public key
+    */
+    pub fn owner_address(&self) -> String {
+        match self.signature_type {
+            SignerMap::Ethereum => ethereum_address(&self.owner),
+            _ => base64_url::encode(&Sha256::digest(&self.owner))
+        }
+    }
+
     pub fn target(&self) -> String {
         let target_base64 = base64_url::encode(&self.target);
         target_base64
@@ -416,18 +566,61 @@ impl DataItem {
         self.tags.clone()
     }
 
+    // the value of the first tag called name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|tag| tag.name == name).map(|tag| tag.value.as_str())
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.name == name)
+    }
+
+    // every value of the tags called name in order, ans-104 allows names to repeat
+    pub fn tags_named(&self, name: &str) -> Vec<&str> {
+        self.tags.iter().filter(|tag| tag.name == name).map(|tag| tag.value.as_str()).collect()
+    }
+
+    pub fn data_bytes(&self) -> Vec<u8> {
+        match &self.data {
+            Data::Bytes(d) => d.clone(),
+            Data::None => vec![]
+        }
+    }
+
+    /*
+        utf-8 data as is, binary data is base64url
+        encoded so it survives json serialization
+    */
     pub fn data(&self) -> Option<String> {
         match &self.data {
             Data::Bytes(d) => {
                 match String::from_utf8(d.clone()) {
                     Ok(s) => Some(s),
-                    Err(_) => None, 
+                    Err(_) => Some(base64_url::encode(d)),
                 }
             },
             Data::None => None
         }
     }
 
+    // whether data() returns the data as is rather than base64url encoded
+    pub fn data_is_utf8(&self) -> bool {
+        match &self.data {
+            Data::Bytes(d) => std::str::from_utf8(d).is_ok(),
+            Data::None => true
+        }
+    }
+
+    // the byte length a signature of this item's signature type has
+    pub fn signature_length(&self) -> usize {
+        self.signature_type.get_config().sig_length
+    }
+
+    // the signature type bytes, u16::MAX for an unsupported type
+    pub fn signature_type(&self) -> u16 {
+        self.signature_type.as_u16()
+    }
+
     pub fn signature(&self) -> String {
         let sig_base64 = base64_url::encode(&self.signature);
         sig_base64
@@ -466,6 +659,19 @@ mod tests {
         assert_eq!("-oM8CYgbqsRcpI3tE_cpGM3kgDlamnYjSGA4nptPao0".to_string(), data_item.target());
     }
 
+    #[test]
+    fn test_tag_lookup() {
+        let item_bytes = base64_url::decode(&ITEM_STR.to_string()).expect("failed to encode data item");
+        let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
+        assert_eq!(data_item.tag("ao-type"), Some("message"));
+        assert_eq!(data_item.tag("Missing"), None);
+        assert!(data_item.has_tag("function"));
+        assert!(!data_item.has_tag("Missing"));
+        // the item carries Data-Protocol twice
+        assert_eq!(data_item.tags_named("Data-Protocol"), vec!["ao", "ao"]);
+        assert!(data_item.tags_named("Missing").is_empty());
+    }
+
     #[test]
     fn test_is_signed() {
         let d_item_string = ITEM_STR.to_string();
@@ -489,4 +695,56 @@ mod tests {
         let bundle_bytes = data_bundle.to_bytes();
         assert!(bundle_bytes.is_ok(), "Bundling failed");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bundle_from_bytes() {
+        let item_bytes = base64_url::decode(&ITEM_STR.to_string()).expect("failed to encode data item");
+        let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
+        let tags = vec![
+            Tag::new("Bundle-Format", "binary"),
+            Tag::new("Bundle-Version", "2.0.0"),
+        ];
+        let mut data_bundle = DataBundle::new(tags.clone());
+        data_bundle.add_item(data_item.clone());
+
+        let mut wrapper = DataItem::new(vec![], data_bundle.to_bytes().unwrap(), tags.clone(), vec![5; 512]).unwrap();
+        wrapper.signature = vec![6; 512];
+
+        let parsed = DataBundle::from_bytes(wrapper.as_bytes().unwrap()).expect("failed to parse bundle");
+        assert_eq!(parsed.tags, tags);
+        assert_eq!(parsed.items.len(), 1);
+        assert_eq!(parsed.items[0].id(), data_item.id());
+    }
+
+    #[test]
+    fn test_serialized_len() {
+        let item_bytes = base64_url::decode(&ITEM_STR.to_string()).expect("failed to encode data item");
+        let data_item = DataItem::from_bytes(item_bytes.clone()).expect("failed to build data item");
+        assert_eq!(data_item.serialized_len().unwrap(), item_bytes.len());
+
+        let tags = vec![Tag::new("Bundle-Format", "binary")];
+        let mut wrapper = DataItem::new(vec![7; 32], item_bytes, tags, vec![5; 512]).unwrap();
+        let unsigned_len = wrapper.serialized_len().unwrap();
+        wrapper.signature = vec![6; 512];
+        assert_eq!(unsigned_len, wrapper.as_bytes().unwrap().len());
+    }
+
+    #[test]
+    fn test_verify_item_count() {
+        let item_bytes = base64_url::decode(&ITEM_STR.to_string()).expect("failed to encode data item");
+        let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
+        let tags = vec![Tag::new("Bundle-Format", "binary")];
+        let mut data_bundle = DataBundle::new(tags.clone());
+        for _ in 0..3 {
+            data_bundle.add_item(data_item.clone());
+        }
+
+        let mut wrapper = DataItem::new(vec![], data_bundle.to_bytes().unwrap(), tags, vec![5; 512]).unwrap();
+        wrapper.signature = vec![6; 512];
+
+        let parsed = DataBundle::from_bytes(wrapper.as_bytes().unwrap()).expect("failed to parse bundle");
+        assert!(parsed.verify_item_count(3).is_ok());
+        assert!(parsed.verify_item_count(2).is_err());
+        assert!(parsed.verify_item_count(4).is_err());
+    }
+}
@@ -1,7 +1,10 @@
+use std::str::FromStr;
+
 use async_trait::async_trait;
 use serde::Deserialize;
+use bundlr_sdk::tags::Tag;
 
-pub use super::json::{Message, Process, PaginatedMessages, JsonErrorType};
+pub use super::json::{Message, Process, PaginatedMessages, JsonErrorType, cursor_position, cursor_timestamp};
 pub use super::router::{Scheduler, ProcessScheduler};
 
 /*
@@ -9,7 +12,7 @@ Interfaces for core dependencies. Implement these traits
 in clients etc... to inject side effects into the core
 */
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct NetworkInfo {
     pub height: String,
     pub current: String
@@ -19,6 +22,12 @@ pub struct NetworkInfo {
 pub trait Gateway: Send + Sync  {
     async fn check_head(&self, tx_id: String) -> Result<bool, String>;
     async fn network_info(&self) -> Result<NetworkInfo, String>;
+    // network info from a given gateway rather than the configured one
+    async fn network_info_from(&self, gateway_url: &str) -> Result<NetworkInfo, String>;
+    // the timestamp in seconds of the block at the given height
+    async fn block_timestamp(&self, height: i64) -> Result<i64, String>;
+    // the tags of a transaction, as indexed by the gateway
+    async fn tx_tags(&self, tx_id: &str) -> Result<Vec<Tag>, String>;
 }
 
 pub trait Wallet: Send + Sync  {
@@ -37,6 +46,54 @@ pub trait Log: Send + Sync {
     fn error(&self, message: String);
 }
 
+/*
+    tracing spans around the phases of a flow, the
+    default methods make an implementation a no-op
+*/
+pub trait Tracer: Send + Sync {
+    fn start_span(&self, _name: &str, _attributes: Vec<(String, String)>) -> u64 {
+        0
+    }
+    fn end_span(&self, _span_id: u64, _attributes: Vec<(String, String)>) {}
+}
+
+/*
+    histograms and counters for alerting, the default
+    methods make an implementation a no-op
+*/
+pub trait Metrics: Send + Sync {
+    fn observe(&self, _name: &str, _value: f64) {}
+    fn increment(&self, _name: &str) {}
+}
+
+/*
+    checks the proof of payment or stake an item
+    carries in its Payment tag before it is scheduled,
+    payment is None when the item has no Payment tag
+*/
+#[async_trait]
+pub trait PaymentVerifier: Send + Sync {
+    async fn verify_payment(&self, item_id: &str, owner: &str, payment: Option<String>) -> Result<bool, String>;
+}
+
+// who made an admin mutation, what it was and its parameters
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub actor: String,
+    pub action: String,
+    pub parameters: serde_json::Value,
+    pub timestamp: i64
+}
+
+/*
+    receives an event for every admin mutation, a
+    failure to record is reported back to the flow
+*/
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, event: &AuditEvent) -> Result<(), String>;
+}
+
 pub trait ScheduleProvider {
     fn epoch(&self) -> String;
     fn nonce(&self) -> String;
@@ -44,12 +101,562 @@ pub trait ScheduleProvider {
     fn hash_chain(&self) -> String;
 }
 
+/*
+    how tag order is enforced against the configured
+    canonical order. Strict rejects inner items whose
+    tags are out of order, Normalize reorders the outer
+    bundle tags only, inner tags are covered by the
+    item signature so they are never rewritten.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagOrderMode {
+    None,
+    Strict,
+    Normalize
+}
+
+impl FromStr for TagOrderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(TagOrderMode::None),
+            "strict" => Ok(TagOrderMode::Strict),
+            "normalize" => Ok(TagOrderMode::Normalize),
+            _ => Err(format!("invalid tag order mode {}", s))
+        }
+    }
+}
+
+/*
+    how much the verifier checks. strict checks the
+    signature type, the signature of arweave, ed25519
+    and ethereum items, the tag limits, an ao-load
+    transaction on the gateway, the tag order, variant
+    and shape and a reused anchor, each as configured.
+    lenient does the same without going to the
+    gateway, for trusted writers
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerificationMode {
+    Strict,
+    Lenient
+}
+
+impl FromStr for VerificationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(VerificationMode::Strict),
+            "lenient" => Ok(VerificationMode::Lenient),
+            _ => Err(format!("invalid verification mode {}", s))
+        }
+    }
+}
+
+/*
+    what the verifier does with an item whose tag
+    count is out of proportion to its data size
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShapeCheckMode {
+    Off,
+    Log,
+    Reject
+}
+
+impl FromStr for ShapeCheckMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ShapeCheckMode::Off),
+            "log" => Ok(ShapeCheckMode::Log),
+            "reject" => Ok(ShapeCheckMode::Reject),
+            _ => Err(format!("invalid shape check mode {}", s))
+        }
+    }
+}
+
+/*
+    what reads do with messages older than their
+    process Message-TTL, they are never deleted
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpiredMessageMode {
+    // flag each message with expired
+    Mark,
+    // leave expired messages out of pages
+    Filter
+}
+
+impl FromStr for ExpiredMessageMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mark" => Ok(ExpiredMessageMode::Mark),
+            "filter" => Ok(ExpiredMessageMode::Filter),
+            _ => Err(format!("invalid expired message mode {}", s))
+        }
+    }
+}
+
+/*
+    when a write responds, once its message is
+    saved or only once its bundle is uploaded too
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AckMode {
+    // saved with the upload left to run after the response
+    Persisted,
+    #[default]
+    Uploaded
+}
+
+impl FromStr for AckMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "persisted" => Ok(AckMode::Persisted),
+            "uploaded" => Ok(AckMode::Uploaded),
+            _ => Err(format!("invalid ack mode {}", s))
+        }
+    }
+}
+
+/*
+    the order messages of a process are listed in by
+    timestamp then nonce, from is where the listing
+    starts in either order so a next_cursor pages the
+    same way
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("invalid sort order {}", s))
+        }
+    }
+}
+
+/*
+    derived fields added under decoded on read
+    responses, the raw tags are left untouched
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadTransform {
+    // the Quantity tag as an integer
+    Quantity,
+    // the su timestamp as ISO-8601
+    Timestamp
+}
+
+impl FromStr for ReadTransform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quantity" => Ok(ReadTransform::Quantity),
+            "timestamp" => Ok(ReadTransform::Timestamp),
+            _ => Err(format!("invalid read transform {}", s))
+        }
+    }
+}
+
+// a Data-Protocol and Type pair, written as protocol:type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolType {
+    pub protocol: String,
+    pub item_type: String
+}
+
+impl FromStr for ProtocolType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((protocol, item_type)) if !protocol.is_empty() && !item_type.is_empty() => Ok(ProtocolType {
+                protocol: protocol.to_string(),
+                item_type: item_type.to_string()
+            }),
+            _ => Err(format!("invalid protocol type pair {}", s))
+        }
+    }
+}
+
 pub trait Config: Send + Sync {
     fn su_wallet_path(&self) -> String;
     fn upload_node_url(&self) -> String;
     fn gateway_url(&self) -> String;
     fn mode(&self) -> String;
     fn scheduler_list_path(&self) -> String;
+
+    /*
+        optional settings, the defaults here
+        are used when a value is not configured
+    */
+
+    // tag every outer bundle with the SU-Version tag
+    fn enable_su_version_tag(&self) -> bool {
+        true
+    }
+
+    // the ANS-104 Bundle-Version tag of the bundles the su builds
+    fn bundle_version(&self) -> String {
+        "2.0.0".to_string()
+    }
+
+    fn tag_order_mode(&self) -> TagOrderMode {
+        TagOrderMode::None
+    }
+
+    // binaries larger than this many bytes are uploaded in chunks
+    fn upload_chunk_threshold(&self) -> usize {
+        10 * 1024 * 1024
+    }
+
+    /*
+        set the target of the outer bundle item to the
+        process id, by default the outer target is empty
+    */
+    fn outer_target_process(&self) -> bool {
+        false
+    }
+
+    // reject messages whose data exceeds the process Memory-Limit tag
+    fn enforce_memory_limit(&self) -> bool {
+        false
+    }
+
+    fn verification_mode(&self) -> VerificationMode {
+        VerificationMode::Strict
+    }
+
+    fn shape_check_mode(&self) -> ShapeCheckMode {
+        ShapeCheckMode::Off
+    }
+
+    // seconds a tx found by check_head is served from the verifier cache
+    fn check_head_ttl_seconds(&self) -> u64 {
+        60
+    }
+
+    // seconds a tx check_head did not find is served from the verifier cache
+    fn check_head_negative_ttl_seconds(&self) -> u64 {
+        5
+    }
+
+    // seconds the full bytes of an item that passed verification are not verified again, 0 disables it
+    fn verification_cache_ttl_seconds(&self) -> u64 {
+        30
+    }
+
+    // seconds the gateway network info is served before it is refreshed
+    fn network_info_ttl_seconds(&self) -> u64 {
+        30
+    }
+
+    // the most tags a data item may carry, as arweave allows
+    fn max_tag_count(&self) -> usize {
+        128
+    }
+
+    // the most bytes the names and values of the tags of an item may add up to
+    fn max_tag_bytes(&self) -> usize {
+        3072
+    }
+
+    // items with fewer tags than this are never considered suspicious
+    fn shape_check_min_tags(&self) -> usize {
+        32
+    }
+
+    // bytes of data expected for every tag on a suspiciously tagged item
+    fn shape_check_min_bytes_per_tag(&self) -> usize {
+        16
+    }
+
+    // accepted Variant tag values, when empty the Variant is not checked
+    fn allowed_variants(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /*
+        accepted signature types (1 arweave, 3 ethereum etc..)
+        when empty any signature type is accepted
+    */
+    fn allowed_signature_types(&self) -> Vec<u16> {
+        vec![]
+    }
+
+    // accepted Data-Protocol tag values, items without one are rejected
+    fn allowed_data_protocols(&self) -> Vec<String> {
+        vec!["ao".to_string()]
+    }
+
+    /*
+        accepted Data-Protocol and Type pairs. a Type named
+        in a pair may only be sent under the protocols it is
+        paired with, types named in no pair are not checked
+    */
+    fn protocol_type_matrix(&self) -> Vec<ProtocolType> {
+        vec![ProtocolType { protocol: "ao".to_string(), item_type: "Process".to_string() }]
+    }
+
+    // gateway urls a write may ask to read network info from
+    fn allowed_gateways(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // fail writes hinting an unlisted gateway instead of ignoring the hint
+    fn reject_unknown_gateway_hints(&self) -> bool {
+        false
+    }
+
+    /*
+        addresses whose signed outer bundles are scheduled
+        and stored as they are, empty accepts none
+    */
+    fn trusted_bundle_signers(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /*
+        reject a data item whose owner already used
+        its anchor on a message of the same process
+    */
+    fn reject_reused_anchors(&self) -> bool {
+        false
+    }
+
+    /*
+        milliseconds a write may take before it counts
+        as an slo breach in the metrics, 0 counts none
+    */
+    fn write_latency_slo_ms(&self) -> u64 {
+        0
+    }
+
+    // messages a subscriber may fall behind by before it skips the oldest, at least 1
+    fn message_subscription_buffer(&self) -> usize {
+        1024
+    }
+
+    /*
+        messages per epoch, the epoch of a process rolls
+        and is checkpointed every epoch_length messages,
+        0 keeps every message in epoch 0
+    */
+    fn epoch_length(&self) -> i32 {
+        0
+    }
+
+    /*
+        hashed ahead of the process id into the nonce 0
+        hash chain, empty hashes the process id alone
+    */
+    fn hash_chain_seed_domain(&self) -> String {
+        "".to_string()
+    }
+
+    // log the full detail of one in this many builds, 0 or 1 logs every build
+    fn verbose_log_sample_rate(&self) -> usize {
+        1
+    }
+
+    fn read_transforms(&self) -> Vec<ReadTransform> {
+        vec![]
+    }
+
+    /*
+        process creations running at once, further
+        creations are rejected until one finishes.
+        message writes are not counted against it
+    */
+    fn max_concurrent_process_creations(&self) -> usize {
+        10
+    }
+
+    /*
+        a process item submitted again returns the process
+        as it stands rather than being built again
+    */
+    fn idempotent_process_creation(&self) -> bool {
+        false
+    }
+
+    /*
+        seconds a message timestamp may sit either side
+        of the time of its block before it is flagged
+    */
+    fn timestamp_window_seconds(&self) -> i64 {
+        3600
+    }
+
+    // zstd compress message binaries as they are stored
+    fn compress_binaries(&self) -> bool {
+        false
+    }
+
+    // read back each binary as it is stored and check it parses to the written item before acking
+    fn verify_stored_binaries(&self) -> bool {
+        false
+    }
+
+    fn expired_message_mode(&self) -> ExpiredMessageMode {
+        ExpiredMessageMode::Mark
+    }
+
+    // refuse to start when the signer fails the self test
+    fn self_test_on_startup(&self) -> bool {
+        false
+    }
+
+    // record admin mutations in the database rather than dropping them
+    fn audit_admin_actions(&self) -> bool {
+        false
+    }
+
+    // bearer token the admin routes require, empty disables them
+    fn admin_token(&self) -> String {
+        "".to_string()
+    }
+
+    /*
+        serialized size a page of messages is cut at,
+        the rest is left for the next page. 0 for no
+        limit beyond the item count
+    */
+    fn max_response_bytes(&self) -> usize {
+        0
+    }
+
+    /*
+        store items without uploading them, they are
+        uploaded later by the flush_uploads admin flow
+    */
+    fn defer_uploads(&self) -> bool {
+        false
+    }
+
+    // load is high when writes in progress near this cap
+    fn max_in_flight_writes(&self) -> usize {
+        100
+    }
+
+    // load is high when pending uploads near this cap
+    fn max_upload_backlog(&self) -> usize {
+        1000
+    }
+
+    // include the load level in write responses
+    fn load_level_in_responses(&self) -> bool {
+        false
+    }
+
+    // tag names in canonical order, unlisted tags are unordered
+    fn canonical_tag_order(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // every setting with its effective value, for ops visibility
+    fn values(&self) -> Vec<ConfigValue> {
+        config_values(self)
+    }
+}
+
+/*
+    a single configuration setting, secret values
+    are redacted before they leave the su
+*/
+#[derive(Debug, Clone)]
+pub struct ConfigValue {
+    pub name: String,
+    pub value: String,
+    pub secret: bool
+}
+
+impl ConfigValue {
+    pub fn new(name: &str, value: impl ToString) -> Self {
+        ConfigValue { name: name.to_string(), value: value.to_string(), secret: false }
+    }
+
+    pub fn secret(name: &str, value: impl ToString) -> Self {
+        ConfigValue { name: name.to_string(), value: value.to_string(), secret: true }
+    }
+}
+
+/*
+    the settings every Config exposes, implementations
+    holding extra settings (credentials etc..) append
+    them to this list in their own values
+*/
+pub fn config_values<C: Config + ?Sized>(config: &C) -> Vec<ConfigValue> {
+    vec![
+        ConfigValue::new("SU_WALLET_PATH", config.su_wallet_path()),
+        ConfigValue::new("UPLOAD_NODE_URL", config.upload_node_url()),
+        ConfigValue::new("GATEWAY_URL", config.gateway_url()),
+        ConfigValue::new("MODE", config.mode()),
+        ConfigValue::new("SCHEDULER_LIST_PATH", config.scheduler_list_path()),
+        ConfigValue::new("ENABLE_SU_VERSION_TAG", config.enable_su_version_tag()),
+        ConfigValue::new("BUNDLE_VERSION", config.bundle_version()),
+        ConfigValue::new("TAG_ORDER_MODE", format!("{:?}", config.tag_order_mode()).to_lowercase()),
+        ConfigValue::new("UPLOAD_CHUNK_THRESHOLD", config.upload_chunk_threshold()),
+        ConfigValue::new("OUTER_TARGET_PROCESS", config.outer_target_process()),
+        ConfigValue::new("ENFORCE_MEMORY_LIMIT", config.enforce_memory_limit()),
+        ConfigValue::new("CHECK_HEAD_TTL_SECONDS", config.check_head_ttl_seconds()),
+        ConfigValue::new("CHECK_HEAD_NEGATIVE_TTL_SECONDS", config.check_head_negative_ttl_seconds()),
+        ConfigValue::new("VERIFICATION_CACHE_TTL_SECONDS", config.verification_cache_ttl_seconds()),
+        ConfigValue::new("NETWORK_INFO_TTL_SECONDS", config.network_info_ttl_seconds()),
+        ConfigValue::new("MAX_TAG_COUNT", config.max_tag_count()),
+        ConfigValue::new("MAX_TAG_BYTES", config.max_tag_bytes()),
+        ConfigValue::new("VERIFICATION_MODE", format!("{:?}", config.verification_mode()).to_lowercase()),
+        ConfigValue::new("SHAPE_CHECK_MODE", format!("{:?}", config.shape_check_mode()).to_lowercase()),
+        ConfigValue::new("SHAPE_CHECK_MIN_TAGS", config.shape_check_min_tags()),
+        ConfigValue::new("SHAPE_CHECK_MIN_BYTES_PER_TAG", config.shape_check_min_bytes_per_tag()),
+        ConfigValue::new("EPOCH_LENGTH", config.epoch_length()),
+        ConfigValue::new("HASH_CHAIN_SEED_DOMAIN", config.hash_chain_seed_domain()),
+        ConfigValue::new("VERBOSE_LOG_SAMPLE_RATE", config.verbose_log_sample_rate()),
+        ConfigValue::new("READ_TRANSFORMS", config.read_transforms().iter().map(|t| format!("{:?}", t).to_lowercase()).collect::<Vec<String>>().join(",")),
+        ConfigValue::new("IDEMPOTENT_PROCESS_CREATION", config.idempotent_process_creation()),
+        ConfigValue::new("TIMESTAMP_WINDOW_SECONDS", config.timestamp_window_seconds()),
+        ConfigValue::new("COMPRESS_BINARIES", config.compress_binaries()),
+        ConfigValue::new("VERIFY_STORED_BINARIES", config.verify_stored_binaries()),
+        ConfigValue::new("EXPIRED_MESSAGE_MODE", format!("{:?}", config.expired_message_mode()).to_lowercase()),
+        ConfigValue::new("SELF_TEST_ON_STARTUP", config.self_test_on_startup()),
+        ConfigValue::new("AUDIT_ADMIN_ACTIONS", config.audit_admin_actions()),
+        ConfigValue::secret("ADMIN_TOKEN", config.admin_token()),
+        ConfigValue::new("MAX_RESPONSE_BYTES", config.max_response_bytes()),
+        ConfigValue::new("DEFER_UPLOADS", config.defer_uploads()),
+        ConfigValue::new("MAX_CONCURRENT_PROCESS_CREATIONS", config.max_concurrent_process_creations()),
+        ConfigValue::new("MAX_IN_FLIGHT_WRITES", config.max_in_flight_writes()),
+        ConfigValue::new("MAX_UPLOAD_BACKLOG", config.max_upload_backlog()),
+        ConfigValue::new("LOAD_LEVEL_IN_RESPONSES", config.load_level_in_responses()),
+        ConfigValue::new("ALLOWED_VARIANTS", config.allowed_variants().join(",")),
+        ConfigValue::new("ALLOWED_SIGNATURE_TYPES", config.allowed_signature_types().iter().map(|t| t.to_string()).collect::<Vec<String>>().join(",")),
+        ConfigValue::new("ALLOWED_DATA_PROTOCOLS", config.allowed_data_protocols().join(",")),
+        ConfigValue::new("PROTOCOL_TYPE_MATRIX", config.protocol_type_matrix().iter().map(|p| format!("{}:{}", p.protocol, p.item_type)).collect::<Vec<String>>().join(",")),
+        ConfigValue::new("ALLOWED_GATEWAYS", config.allowed_gateways().join(",")),
+        ConfigValue::new("REJECT_UNKNOWN_GATEWAY_HINTS", config.reject_unknown_gateway_hints()),
+        ConfigValue::new("TRUSTED_BUNDLE_SIGNERS", config.trusted_bundle_signers().join(",")),
+        ConfigValue::new("REJECT_REUSED_ANCHORS", config.reject_reused_anchors()),
+        ConfigValue::new("WRITE_LATENCY_SLO_MS", config.write_latency_slo_ms()),
+        ConfigValue::new("MESSAGE_SUBSCRIPTION_BUFFER", config.message_subscription_buffer()),
+        ConfigValue::new("CANONICAL_TAG_ORDER", config.canonical_tag_order().join(",")),
+    ]
 }
 
 #[derive(Debug)]
@@ -63,8 +670,15 @@ impl From<UploaderErrorType> for String {
     }
 }
 
+#[async_trait]
 pub trait Uploader: Send + Sync {
-    fn upload(&self, tx: Vec<u8>) -> Result<(), UploaderErrorType>;
+    async fn upload(&self, tx: Vec<u8>) -> Result<(), UploaderErrorType>;
+    // price in winston to upload byte_len bytes, nothing is uploaded
+    async fn price(&self, byte_len: u64) -> Result<u64, UploaderErrorType>;
+    // number of uploads accepted but not yet completed
+    fn backlog(&self) -> usize {
+        0
+    }
 }
 
 #[derive(Debug)]
@@ -76,24 +690,131 @@ pub enum StoreErrorType {
     IntError(String)
 }
 
+// the stored outer bundle of a message
+pub struct MessageBundle {
+    pub row_id: i32,
+    pub message_id: String,
+    pub timestamp: i64,
+    pub bundle: Vec<u8>
+}
+
+// the messages one owner sent to a process
+pub struct OwnerStats {
+    pub owner: String,
+    pub message_count: i64,
+    pub first_timestamp: i64,
+    pub last_timestamp: i64
+}
+
+// a built bundle written while uploads were deferred
+pub struct PendingUpload {
+    pub row_id: i32,
+    pub item_id: String,
+    pub bundle: Vec<u8>
+}
+
+// the upload state of a pending upload, without its bundle
+#[derive(Debug, Clone)]
+pub struct PendingUploadStatus {
+    pub row_id: i32,
+    pub item_id: String,
+    pub attempts: i32,
+    pub last_error: Option<String>
+}
+
+// the root and boundary nonces of a closed epoch
+#[derive(Debug, Clone)]
+pub struct EpochCheckpoint {
+    pub process_id: String,
+    pub epoch: i32,
+    pub root: String,
+    pub first_nonce: i32,
+    pub last_nonce: i32,
+    pub timestamp: i64
+}
+
+#[async_trait]
 pub trait DataStore: Send + Sync {
-    fn save_process(&self, process: &Process, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
-    fn get_process(&self, process_id_in: &str) -> Result<Process, StoreErrorType>;
-    fn save_message(&self, message: &Message, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
-    fn get_messages(
+    async fn save_process(&self, process: &Process, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
+    async fn get_process(&self, process_id_in: &str) -> Result<Process, StoreErrorType>;
+    async fn get_process_binary(&self, process_id_in: &str) -> Result<Vec<u8>, StoreErrorType>;
+    async fn save_message(&self, message: &Message, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
+    // remove a process or message that was saved but must not be kept, such as one that failed to read back
+    async fn delete_process(&self, process_id_in: &str) -> Result<String, StoreErrorType>;
+    async fn delete_message(&self, message_id_in: &str) -> Result<String, StoreErrorType>;
+    // save all of the messages or, on any failure, none of them
+    async fn save_messages(&self, items: &[(Message, Vec<u8>)]) -> Result<String, StoreErrorType>;
+    async fn get_messages(
         &self,
         process_id_in: &str,
         from: &Option<String>,
         to: &Option<String>,
         limit: &Option<i32>,
+        order: &Option<SortOrder>,
     ) -> Result<PaginatedMessages, StoreErrorType>;
-    fn get_message(&self, message_id_in: &str) -> Result<Message, StoreErrorType>;
-    fn get_latest_message(&self, process_id_in: &str) -> Result<Option<Message>, StoreErrorType>;
-    fn save_process_scheduler(&self, process_scheduler: &ProcessScheduler) -> Result<String, StoreErrorType>;
-    fn get_process_scheduler(&self, process_id_in: &str) -> Result<ProcessScheduler, StoreErrorType>;
-    fn save_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType>;
-    fn update_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType>;
-    fn get_scheduler(&self, row_id_in: &i32) -> Result<Scheduler, StoreErrorType>;
-    fn get_scheduler_by_url(&self, url_in: &String) -> Result<Scheduler, StoreErrorType>;
-    fn get_all_schedulers(&self) -> Result<Vec<Scheduler>, StoreErrorType>;
+    async fn get_message(&self, message_id_in: &str) -> Result<Message, StoreErrorType>;
+    async fn get_message_binary(&self, message_id_in: &str) -> Result<Vec<u8>, StoreErrorType>;
+    async fn get_owner_stats(&self, process_id_in: &str) -> Result<Vec<OwnerStats>, StoreErrorType>;
+    async fn get_message_ids(&self, process_id_in: &str) -> Result<Vec<String>, StoreErrorType>;
+    async fn get_message_count(&self, process_id_in: &str) -> Result<i64, StoreErrorType>;
+    // a round trip to the database, for health checks
+    async fn ping(&self) -> Result<(), StoreErrorType>;
+    // whether owner_in already sent a message to the process with anchor_in
+    async fn anchor_used(&self, process_id_in: &str, owner_in: &str, anchor_in: &str) -> Result<bool, StoreErrorType>;
+    /*
+        whether stored message ids are guaranteed unique,
+        postgres checks the unique index on message_id is
+        in place and valid rather than scanning every row
+    */
+    async fn message_ids_unique(&self) -> Result<bool, StoreErrorType>;
+    async fn find_duplicate_nonces(&self, process_id_in: &str) -> Result<Vec<i32>, StoreErrorType>;
+    async fn get_messages_by_nonce_range(
+        &self,
+        process_id_in: &str,
+        from_nonce: i32,
+        to_nonce: i32,
+    ) -> Result<Vec<Message>, StoreErrorType>;
+    // (nonce, hash chain) of each message of the epoch, by nonce, only those past after_nonce when given
+    async fn get_epoch_hash_chains(&self, process_id_in: &str, epoch_in: i32, after_nonce: Option<i32>) -> Result<Vec<(i32, String)>, StoreErrorType>;
+    // (epoch, message count) of each epoch of the process holding messages, by epoch
+    async fn get_epoch_message_counts(&self, process_id_in: &str) -> Result<Vec<(i32, i64)>, StoreErrorType>;
+    async fn save_epoch_checkpoint(&self, checkpoint: &EpochCheckpoint) -> Result<String, StoreErrorType>;
+    async fn get_epoch_checkpoint(&self, process_id_in: &str, epoch_in: i32) -> Result<EpochCheckpoint, StoreErrorType>;
+    async fn set_process_frozen(&self, process_id_in: &str, frozen: bool) -> Result<String, StoreErrorType>;
+    async fn is_process_frozen(&self, process_id_in: &str) -> Result<bool, StoreErrorType>;
+    async fn save_pending_upload(&self, item_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
+    // pending uploads after after_row_id with fewer than max_attempts failed attempts, in the order they were written
+    async fn get_pending_uploads(&self, after_row_id: i32, max_attempts: i32, limit: i32) -> Result<Vec<PendingUpload>, StoreErrorType>;
+    async fn delete_pending_upload(&self, item_id_in: &str) -> Result<String, StoreErrorType>;
+    // count a failed upload attempt of a pending upload
+    async fn record_upload_failure(&self, item_id_in: &str, error: &str) -> Result<String, StoreErrorType>;
+    // pending uploads after after_row_id and up to to_row_id when set, in the order they were written
+    async fn get_pending_upload_statuses(
+        &self,
+        after_row_id: i32,
+        to_row_id: Option<i32>,
+        limit: i32
+    ) -> Result<Vec<PendingUploadStatus>, StoreErrorType>;
+    // the latest message of the process that is not waiting on an upload
+    async fn get_latest_uploaded_bundle(&self, process_id_in: &str) -> Result<Option<MessageBundle>, StoreErrorType>;
+    async fn count_pending_uploads(&self, process_id_in: &str) -> Result<i64, StoreErrorType>;
+    // the timestamp of the oldest message of the process still waiting on an upload
+    async fn get_oldest_pending_timestamp(&self, process_id_in: &str) -> Result<Option<i64>, StoreErrorType>;
+    async fn save_audit_event(&self, event: &AuditEvent) -> Result<String, StoreErrorType>;
+    async fn get_message_bundles(
+        &self,
+        from: &Option<String>,
+        to: &Option<String>,
+        after_row_id: i32,
+        limit: i32,
+    ) -> Result<Vec<MessageBundle>, StoreErrorType>;
+    async fn update_message_bundle(&self, message_id_in: &str, bundle_in: &[u8]) -> Result<String, StoreErrorType>;
+    async fn get_latest_message(&self, process_id_in: &str) -> Result<Option<Message>, StoreErrorType>;
+    async fn save_process_scheduler(&self, process_scheduler: &ProcessScheduler) -> Result<String, StoreErrorType>;
+    async fn get_process_scheduler(&self, process_id_in: &str) -> Result<ProcessScheduler, StoreErrorType>;
+    async fn save_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType>;
+    async fn update_scheduler(&self, scheduler: &Scheduler) -> Result<String, StoreErrorType>;
+    async fn get_scheduler(&self, row_id_in: &i32) -> Result<Scheduler, StoreErrorType>;
+    async fn get_scheduler_by_url(&self, url_in: &str) -> Result<Scheduler, StoreErrorType>;
+    async fn get_all_schedulers(&self) -> Result<Vec<Scheduler>, StoreErrorType>;
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+use super::json::Message;
+
+/*
+    MessageEvents holds a channel per subscribed
+    process so a subscriber only ever buffers the
+    messages of its own process. a channel is
+    dropped once its last subscriber is gone
+*/
+pub struct MessageEvents {
+    buffer: usize,
+    channels: Mutex<HashMap<String, broadcast::Sender<Message>>>
+}
+
+impl MessageEvents {
+    // buffer must be at least 1, the config rejects anything lower
+    pub fn new(buffer: usize) -> Self {
+        MessageEvents {
+            buffer,
+            channels: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub fn subscribe(&self, process_id: &str) -> broadcast::Receiver<Message> {
+        let mut channels = self.channels.lock().unwrap();
+        channels.retain(|_, sender| sender.receiver_count() > 0);
+        channels.entry(process_id.to_string())
+            .or_insert_with(|| broadcast::channel(self.buffer).0)
+            .subscribe()
+    }
+
+    // a no op when nobody is subscribed to the process
+    pub fn publish(&self, message: &Message) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(&message.process_id) {
+            if sender.send(message.clone()).is_err() {
+                channels.remove(&message.process_id);
+            }
+        }
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+}
@@ -1,10 +1,15 @@
+use std::num::ParseIntError;
+
 
 
 
 use serde::{Serialize, Deserialize}; 
-use sha2::{Digest, Sha256};
 
-use super::bytes::{DataBundle};
+use chrono::{SecondsFormat, TimeZone, Utc};
+
+use super::bytes::{DataBundle, DataItem};
+use super::dal::ReadTransform;
+use super::scheduler::gen_hash_chain;
 use bundlr_sdk::{tags::*};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -36,6 +41,33 @@ impl From<std::num::ParseIntError> for JsonErrorType {
     }
 }
 
+/*
+    how the data of a message or process is held,
+    utf-8 data is kept as is and anything else is
+    base64url encoded so it survives json
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DataEncoding {
+    #[default]
+    Utf8,
+    Base64Url
+}
+
+impl DataEncoding {
+    fn of(item: &DataItem) -> Self {
+        if item.data_is_utf8() { DataEncoding::Utf8 } else { DataEncoding::Base64Url }
+    }
+
+    // the byte length of data held in this encoding
+    pub fn decoded_len(&self, data: &str) -> Result<usize, JsonErrorType> {
+        match self {
+            DataEncoding::Utf8 => Ok(data.len()),
+            DataEncoding::Base64Url => Ok(base64_url::decode(data)?.len())
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MessageInner {
     pub id: String,
@@ -58,6 +90,8 @@ pub struct Process {
     pub tags: Vec<Tag>,
     pub timestamp: i64,
     pub data: Option<String>,
+    #[serde(default)]
+    pub data_encoding: DataEncoding,
     pub anchor: Option<String>,
     pub signature: Option<String>,
 }
@@ -69,6 +103,8 @@ pub struct Message {
     pub owner: Owner,
     pub process_id: String,
     pub data: Option<String>,
+    #[serde(default)]
+    pub data_encoding: DataEncoding,
     pub epoch: i32,
     pub nonce: i32,
     pub timestamp: i64,
@@ -84,6 +120,8 @@ pub struct PaginatedMessages {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PageInfo {
     pub has_next_page: bool,
+    // pass as from to read the next page, None on the last page
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -92,11 +130,112 @@ pub struct Edge {
     pub cursor: String,
 }
 
-pub fn hash(data: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let result = hasher.finalize();
-    result.to_vec()
+/*
+    messages in the connection shape of the arweave
+    graphql transactions query, fields the su has no
+    value for (fee, block id etc..) are left empty
+*/
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GqlConnection {
+    pub page_info: GqlPageInfo,
+    pub edges: Vec<GqlEdge>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GqlPageInfo {
+    pub has_next_page: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GqlEdge {
+    pub cursor: String,
+    pub node: GqlTransaction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GqlTransaction {
+    pub id: String,
+    pub anchor: String,
+    pub signature: String,
+    pub recipient: String,
+    pub owner: Owner,
+    pub fee: GqlAmount,
+    pub quantity: GqlAmount,
+    pub data: GqlData,
+    pub tags: Vec<Tag>,
+    pub block: GqlBlock,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GqlAmount {
+    pub winston: String,
+    pub ar: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GqlData {
+    pub size: String,
+    #[serde(rename = "type")]
+    pub content_type: Option<String>,
+    pub encoding: DataEncoding,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GqlBlock {
+    pub id: Option<String>,
+    pub timestamp: i64,
+    pub height: i64,
+    pub previous: Option<String>,
+}
+
+impl GqlConnection {
+    pub fn from_messages(messages: &PaginatedMessages) -> Result<Self, JsonErrorType> {
+        let edges = messages.edges.iter()
+            .map(|edge| Ok(GqlEdge {
+                cursor: edge.cursor.clone(),
+                node: GqlTransaction::from_message(&edge.node)?,
+            }))
+            .collect::<Result<Vec<GqlEdge>, JsonErrorType>>()?;
+
+        Ok(GqlConnection {
+            page_info: GqlPageInfo { has_next_page: messages.page_info.has_next_page },
+            edges,
+        })
+    }
+}
+
+impl GqlTransaction {
+    pub fn from_message(message: &Message) -> Result<Self, JsonErrorType> {
+        let zero = GqlAmount { winston: "0".to_string(), ar: "0.000000000000".to_string() };
+        let content_type = message.message.tags.iter()
+            .find(|tag| tag.name == "Content-Type")
+            .map(|tag| tag.value.clone());
+        let size = match &message.data {
+            Some(data) => message.data_encoding.decoded_len(data)?,
+            None => 0
+        };
+
+        Ok(GqlTransaction {
+            id: message.message.id.clone(),
+            anchor: message.message.anchor.clone().unwrap_or_default(),
+            signature: message.message.signature.clone(),
+            recipient: message.process_id.clone(),
+            owner: message.owner.clone(),
+            fee: zero.clone(),
+            quantity: zero,
+            data: GqlData { size: size.to_string(), content_type, encoding: message.data_encoding },
+            tags: message.message.tags.clone(),
+            // arweave block timestamps are in seconds, su timestamps in milliseconds
+            block: GqlBlock {
+                id: None,
+                timestamp: message.timestamp / 1000,
+                height: message.block.parse::<i64>()?,
+                previous: None,
+            },
+        })
+    }
 }
 
 impl Process {
@@ -108,9 +247,7 @@ impl Process {
         let data = data_bundle.items[0].data().clone();
         let anchor = data_bundle.items[0].anchor().clone();
         
-        let owner_bytes = base64_url::decode(&owner)?;
-        let address_hash = hash(&owner_bytes);
-        let address = base64_url::encode(&address_hash);
+        let address = data_bundle.items[0].owner_address();
 
         let bundle_tags = data_bundle.tags.clone();
         
@@ -142,20 +279,85 @@ impl Process {
             tags: tags,
             signature: Some(signature),
             anchor: anchor_r,
-            data: data
+            data: data,
+            data_encoding: DataEncoding::of(&data_bundle.items[0])
         })
     }
 }
 
+/*
+    a Quantity as a canonical decimal string, amounts
+    can be far past u64 so they are never parsed into
+    a fixed width integer, None when it is not a
+    non negative integer
+*/
+fn canonical_quantity(value: &str) -> Option<String> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let trimmed = value.trim_start_matches('0');
+    Some(if trimmed.is_empty() { "0".to_string() } else { trimmed.to_string() })
+}
+
 impl Message {
+    /*
+        the derived fields for the given transforms, a
+        field is left out when its source is missing
+        or cannot be decoded
+    */
+    pub fn decoded(&self, transforms: &[ReadTransform]) -> serde_json::Map<String, serde_json::Value> {
+        let mut decoded = serde_json::Map::new();
+        for transform in transforms {
+            match transform {
+                ReadTransform::Quantity => {
+                    let quantity = self.message.tags.iter()
+                        .find(|tag| tag.name == "Quantity")
+                        .and_then(|tag| canonical_quantity(&tag.value));
+                    if let Some(quantity) = quantity {
+                        decoded.insert("Quantity".to_string(), quantity.into());
+                    }
+                },
+                ReadTransform::Timestamp => {
+                    if let Some(time) = Utc.timestamp_millis_opt(self.timestamp).single() {
+                        decoded.insert("Timestamp".to_string(), time.to_rfc3339_opts(SecondsFormat::Millis, true).into());
+                    }
+                }
+            }
+        }
+        decoded
+    }
+
     pub fn from_bundle(data_bundle: &DataBundle) -> Result<Self, JsonErrorType> {
-        let id = data_bundle.items[0].id().clone();
-        let tags = data_bundle.items[0].tags();
-        let owner = data_bundle.items[0].owner().clone();
-        let target = data_bundle.items[0].target().clone();
-        let signature = data_bundle.items[0].signature().clone();
-        let data = data_bundle.items[0].data().clone();
-        let anchor = data_bundle.items[0].anchor().clone();
+        Self::from_bundle_item(data_bundle, &data_bundle.items[0])
+    }
+
+    /*
+        every message of a batch bundle, the first takes
+        the Nonce and Hash-Chain tags and each after it the
+        next nonce and the link from the message before
+    */
+    pub fn from_batch(data_bundle: &DataBundle) -> Result<Vec<Self>, JsonErrorType> {
+        let mut messages: Vec<Self> = Vec::with_capacity(data_bundle.items.len());
+        for item in &data_bundle.items {
+            let mut message = Self::from_bundle_item(data_bundle, item)?;
+            if let Some(previous) = messages.last() {
+                message.nonce = previous.nonce + 1;
+                message.hash_chain = gen_hash_chain(&previous.hash_chain, Some(&previous.message.id))
+                    .map_err(JsonErrorType::JsonError)?;
+            }
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    fn from_bundle_item(data_bundle: &DataBundle, item: &DataItem) -> Result<Self, JsonErrorType> {
+        let id = item.id().clone();
+        let tags = item.tags();
+        let owner = item.owner().clone();
+        let target = item.target().clone();
+        let signature = item.signature().clone();
+        let data = item.data().clone();
+        let anchor = item.anchor().clone();
 
         let ac = anchor.clone();
         let anchor_r = match &*anchor {
@@ -170,19 +372,20 @@ impl Message {
             anchor: anchor_r
         };
 
-        let owner_bytes = base64_url::decode(&owner)?;
-        let address_hash = hash(&owner_bytes);
-        let address = base64_url::encode(&address_hash);
+        let address = item.owner_address();
 
         let owner = Owner {
             address: address,
             key: owner,
         };
 
-        let process_id = target;
-
         let bundle_tags = data_bundle.tags.clone();
 
+        // an assignment does not target the process it is scheduled in
+        let process_id = bundle_tags.iter().find(|tag| tag.name == "Process")
+            .map(|tag| tag.value.clone())
+            .unwrap_or(target);
+
         let block_tag = bundle_tags.iter().find(|tag| tag.name == "Block-Height")
             .ok_or("Block-Height tag not found")?;
 
@@ -209,6 +412,7 @@ impl Message {
             owner,
             process_id,
             data,
+            data_encoding: DataEncoding::of(item),
             block,
             epoch,
             nonce,
@@ -222,17 +426,56 @@ impl Message {
 impl PaginatedMessages {
 
     pub fn from_messages(messages: Vec<Message>, has_next_page: bool) -> Result<Self, JsonErrorType> {
-        let page_info = PageInfo { has_next_page };
+        let next_cursor = match has_next_page {
+            true => messages.last().map(|message| encode_cursor(message.timestamp, message.nonce)),
+            false => None
+        };
+        let page_info = PageInfo { has_next_page, next_cursor };
 
         let edges = messages.into_iter().map(|message| Edge {
-            node: message.clone(),
-            cursor: message.timestamp.clone().to_string(),
+            cursor: encode_cursor(message.timestamp, message.nonce),
+            node: message,
         }).collect();
 
         Ok(PaginatedMessages { page_info, edges })
     }
 }
 
+/*
+    page cursors are opaque to clients, they wrap the
+    timestamp and nonce of the last message of a page.
+    the messages of a batch share a timestamp so the
+    nonce is needed to page through the middle of one
+*/
+pub fn encode_cursor(timestamp: i64, nonce: i32) -> String {
+    base64_url::encode(&format!("{}:{}", timestamp, nonce))
+}
+
+/*
+    the timestamp and nonce a listing starts after. a
+    bare timestamp as from has always taken, or a cursor
+    from before nonces were added, has no nonce and so
+    starts after every message at that timestamp
+*/
+pub fn cursor_position(cursor: &str) -> Result<(i64, Option<i32>), ParseIntError> {
+    match cursor.parse::<i64>() {
+        Ok(timestamp) => Ok((timestamp, None)),
+        Err(e) => {
+            let decoded = base64_url::decode(cursor).ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or(e)?;
+            match decoded.split_once(':') {
+                Some((timestamp, nonce)) => Ok((timestamp.parse::<i64>()?, Some(nonce.parse::<i32>()?))),
+                None => Ok((decoded.parse::<i64>()?, None))
+            }
+        }
+    }
+}
+
+// a cursor from next_cursor, or a bare timestamp as from has always taken
+pub fn cursor_timestamp(cursor: &str) -> Result<i64, ParseIntError> {
+    cursor_position(cursor).map(|(timestamp, _)| timestamp)
+}
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::dal::Config;
+
+/*
+    how busy the su is, returned to clients
+    so they can throttle themselves
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadLevel {
+    Low,
+    Medium,
+    High
+}
+
+impl LoadLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoadLevel::Low => "low",
+            LoadLevel::Medium => "medium",
+            LoadLevel::High => "high"
+        }
+    }
+}
+
+/*
+    LoadMonitor counts the writes currently in
+    progress, combined with the upload backlog
+    this gives the load level. process creations
+    are also counted on their own to cap them
+*/
+pub struct LoadMonitor {
+    in_flight: AtomicUsize,
+    creations: AtomicUsize
+}
+
+// decrements the in flight count when dropped
+pub struct InFlight<'a> {
+    monitor: &'a LoadMonitor
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.monitor.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// frees a process creation slot when dropped
+pub struct Creation<'a> {
+    monitor: &'a LoadMonitor
+}
+
+impl Drop for Creation<'_> {
+    fn drop(&mut self) {
+        self.monitor.creations.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl LoadMonitor {
+    pub fn new() -> Self {
+        LoadMonitor {
+            in_flight: AtomicUsize::new(0),
+            creations: AtomicUsize::new(0)
+        }
+    }
+
+    // None when max creations are already running
+    pub fn try_enter_creation(&self, max: usize) -> Option<Creation<'_>> {
+        self.creations
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| (count < max).then_some(count + 1))
+            .ok()?;
+        Some(Creation { monitor: self })
+    }
+
+    pub fn creations(&self) -> usize {
+        self.creations.load(Ordering::SeqCst)
+    }
+
+    pub fn enter(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight { monitor: self }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /*
+        the level follows whichever of the in flight
+        writes or upload backlog is closest to its cap
+    */
+    pub fn level(&self, upload_backlog: usize, config: &dyn Config) -> LoadLevel {
+        let in_flight = self.in_flight() as f64 / config.max_in_flight_writes().max(1) as f64;
+        let backlog = upload_backlog as f64 / config.max_upload_backlog().max(1) as f64;
+        let usage = in_flight.max(backlog);
+        if usage >= 0.8 {
+            LoadLevel::High
+        } else if usage >= 0.5 {
+            LoadLevel::Medium
+        } else {
+            LoadLevel::Low
+        }
+    }
+}
+
+impl Default for LoadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
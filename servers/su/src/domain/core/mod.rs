@@ -11,6 +11,12 @@ pub mod dal;
 // mutex locked scheduling data
 pub mod scheduler;
 
+// in flight and backlog based load level
+pub mod load;
+
+// per process channels of saved messages for subscribers
+pub mod events;
+
 // main business logic
 pub mod flows;
 
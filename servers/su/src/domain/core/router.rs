@@ -3,6 +3,7 @@ use serde::Deserialize;
 use tokio::{fs::File, io::AsyncReadExt};
 use crate::domain::{ flows::{Deps, init_builder} };
 use crate::domain::core::dal::StoreErrorType;
+use crate::domain::core::builder::scheduled_process_id;
 
 /*
     The code in this file only runs on a su that is
@@ -13,12 +14,14 @@ use crate::domain::core::dal::StoreErrorType;
     a file. It is a basic load balancer implementation
 */
 
+#[derive(Clone)]
 pub struct Scheduler {
     pub row_id: Option<i32>,
     pub url: String,
     pub process_count: i32
 }
 
+#[derive(Clone)]
 pub struct ProcessScheduler {
     pub row_id: Option<i32>,
     pub process_id: String,
@@ -50,13 +53,13 @@ pub async fn init_schedulers(deps: Arc<Deps>) -> Result<String, String> {
         if the scheduler doesnt exist yet create it
     */
     for entry in urls {
-        if let Err(StoreErrorType::NotFound(_)) = deps.data_store.get_scheduler_by_url(&entry.url) {
+        if let Err(StoreErrorType::NotFound(_)) = deps.data_store.get_scheduler_by_url(&entry.url).await {
             let scheduler = Scheduler {
                 row_id: None,
                 url: entry.url.clone(),
                 process_count: 0
             };
-            deps.data_store.save_scheduler(&scheduler)?;
+            deps.data_store.save_scheduler(&scheduler).await?;
             deps.logger.log(format!("saved new scheduler: {}", entry.url));
         }
     }
@@ -73,8 +76,8 @@ pub async fn redirect_process_id(deps: Arc<Deps>, process_id: Option<String>) ->
     let pid = process_id.ok_or("No process-id query parameter provided")?;
 
     // every other process_id, redirect
-    let process_scheduler = deps.data_store.get_process_scheduler(&pid)?;
-    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id)?;
+    let process_scheduler = deps.data_store.get_process_scheduler(&pid).await?;
+    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id).await?;
     Ok(Some(scheduler.url))
 }
 
@@ -84,7 +87,7 @@ pub async fn redirect_tx_id(deps: Arc<Deps>, tx_id: String, process_id: Option<S
         return Ok(None);
     }
 
-    let process_to_query = match deps.data_store.get_process_scheduler(&tx_id) {
+    let process_to_query = match deps.data_store.get_process_scheduler(&tx_id).await {
         Ok(_) => tx_id,
         /*
             we didn't find a process scheduler based on the tx_id
@@ -93,8 +96,8 @@ pub async fn redirect_tx_id(deps: Arc<Deps>, tx_id: String, process_id: Option<S
         Err(_) => process_id.ok_or("Unable to locate process, if this is a message id query be sure to pass the process-id query parameter")?,
     };
 
-    let process_scheduler = deps.data_store.get_process_scheduler(&process_to_query)?;
-    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id)?;
+    let process_scheduler = deps.data_store.get_process_scheduler(&process_to_query).await?;
+    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id).await?;
     Ok(Some(scheduler.url))
 }
 
@@ -106,23 +109,22 @@ pub async fn redirect_data_item(deps: Arc<Deps>, input: Vec<u8>) -> Result<Optio
     }
     let builder = init_builder(&deps)?;
     let item = builder.parse_data_item(input.clone())?;
-    let tags = item.tags().clone();
     let id = item.id().clone();
-    let target = item.target().clone();
-    let type_tag = tags.iter().find(|tag| tag.name == "Type")
+    let target = scheduled_process_id(&item);
+    let type_tag = item.tag("Type")
         .ok_or("Cannot redirect data item, invalid Type Tag")?;
     
-    match type_tag.value.as_str() {
+    match type_tag {
         "Process" => {
 
              /*
                 new process so we need to generate a
                 process_schedulers record and return the url
             */
-            let mut schedulers = deps.data_store.get_all_schedulers()?;
+            let mut schedulers = deps.data_store.get_all_schedulers().await?;
             if let Some(min_scheduler) = schedulers.iter_mut().min_by_key(|s| s.process_count) {
                 min_scheduler.process_count += 1;
-                deps.data_store.update_scheduler(min_scheduler)?;
+                deps.data_store.update_scheduler(min_scheduler).await?;
 
                 let scheduler_row_id = if let Some(min_scheduler_row_id) = min_scheduler.row_id {
                     min_scheduler_row_id
@@ -139,21 +141,21 @@ pub async fn redirect_data_item(deps: Arc<Deps>, input: Vec<u8>) -> Result<Optio
                     scheduler_row_id: scheduler_row_id,
                     process_id: id
                 };
-                deps.data_store.save_process_scheduler(&process_scheduler)?;
+                deps.data_store.save_process_scheduler(&process_scheduler).await?;
 
                 Ok(Some(min_scheduler.url.clone()))
             } else {
                 Err("Could not find a scheduler to assign".to_string())
             }
         },
-        "Message" => {
+        "Message" | "Assignment" => {
             /*
                 otherwise, fetch the correct scheduler based
-                on the messages's target
+                on the process the item is scheduled in
             */
-            match deps.data_store.get_process_scheduler(&target) {
+            match deps.data_store.get_process_scheduler(&target).await {
                 Ok(process_scheduler) => {
-                    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id)?;
+                    let scheduler = deps.data_store.get_scheduler(&process_scheduler.scheduler_row_id).await?;
                     Ok(Some(scheduler.url))
                 },
                 Err(_) => Err("Unable to locate scheduler for message target".to_string()),
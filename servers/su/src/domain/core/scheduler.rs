@@ -1,4 +1,5 @@
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -7,11 +8,12 @@ use dashmap::DashMap;
 use tokio::sync::Mutex;
 use base64_url;
 
-use crate::domain::core::dal::{ScheduleProvider, Log, DataStore};
+use crate::domain::core::dal::{ScheduleProvider, Log, DataStore, Config, EpochCheckpoint};
 
 pub struct SchedulerDeps {
     pub data_store: Arc<dyn DataStore>,
-    pub logger: Arc<dyn Log>
+    pub logger: Arc<dyn Log>,
+    pub config: Arc<dyn Config>
 }
 
 /*
@@ -49,9 +51,9 @@ impl ProcessScheduler {
     }
 
     /*
-        acquire the lock while also obtaining
-        the info needed epoch, nonce etc.. to 
-        build a valid item in the schedule
+        acquire the lock for the schedule of id.
+        the info it holds is only filled in by
+        update_schedule_info once the lock is taken
     */
     pub async fn acquire_lock(&self, id: String) -> Result<LockedScheduleInfo, String> {
         let locked_schedule_info = {
@@ -68,6 +70,14 @@ impl ProcessScheduler {
         Ok(locked_schedule_info)
     }
 
+    /*
+        fill in the info needed epoch, nonce etc.. to
+        build a valid item in the schedule. it is read
+        from the latest stored message every time, so
+        the first lock after boot carries on from where
+        the schedule was before a restart and a rolled
+        back write leaves no gap
+    */
     pub async fn update_schedule_info<'a>(&'a self, schedule_info: &'a mut ScheduleInfo, id: String) -> Result<&mut ScheduleInfo, String> {
         let (current_epoch, current_nonce, current_hash_chain, current_timestamp) = match fetch_values(self.deps.clone(), &id).await {
             Ok(vals) => vals,
@@ -80,7 +90,37 @@ impl ProcessScheduler {
         Ok(schedule_info)
     }
 
-    
+    /*
+        the schedule info the next item for id would be
+        given, read without taking the lock or closing an
+        epoch so it is only good for previews
+    */
+    pub async fn preview_schedule_info(&self, id: &str) -> Result<ScheduleInfo, String> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("{:?}", e))?
+            .as_millis() as i64;
+        let latest_message = self.deps.data_store
+            .get_latest_message(id).await
+            .map_err(|e| format!("{:?}", e))?;
+
+        match latest_message {
+            Some(previous_message) => {
+                let nonce = previous_message.nonce + 1;
+                let epoch_length = self.deps.config.epoch_length();
+                let epoch = match epoch_length > 0 && nonce % epoch_length == 0 {
+                    true => previous_message.epoch + 1,
+                    false => previous_message.epoch
+                };
+                let hash_chain = gen_hash_chain(&previous_message.hash_chain, Some(&previous_message.message.id))?;
+                Ok(ScheduleInfo { epoch, nonce, timestamp: millis, hash_chain })
+            },
+            None => {
+                let hash_chain = gen_genesis_hash_chain(id, &self.deps.config.hash_chain_seed_domain())?;
+                Ok(ScheduleInfo { epoch: 0, nonce: 0, timestamp: millis, hash_chain })
+            }
+        }
+    }
 }
 
 
@@ -105,30 +145,136 @@ impl DecodeHash for [u8; 32] {
     }
 }
 
-fn gen_hash_chain(previous_or_seed: &str, previous_message_id: Option<&str>) -> Result<String, String> {
+/*
+    the hash chain primitive, sha256 of the previous
+    message id followed by the previous hash chain.
+    the first message of a process hashes only the
+    process id, pass an empty id for it
+*/
+pub fn compute_hash_chain(prev_hash: &[u8], id: &[u8]) -> Vec<u8> {
     let mut hasher = Sha256::new();
+    hasher.update(id);
+    hasher.update(prev_hash);
+    hasher.finalize().to_vec()
+}
 
-    let prev_bytes: [u8; 32] = match DecodeHash::from(previous_or_seed) {
-        Ok(pb) => pb,
-        Err(e) => return Err(e),
-    };
-    
-    match previous_message_id {
+pub fn gen_hash_chain(previous_or_seed: &str, previous_message_id: Option<&str>) -> Result<String, String> {
+    let prev_bytes: [u8; 32] = DecodeHash::from(previous_or_seed)?;
+
+    let id_bytes: Vec<u8> = match previous_message_id {
         Some(id) => {
-            let id_bytes: [u8; 32] = match DecodeHash::from(id) {
-                Ok(idb) => idb,
-                Err(e) => return Err(e),
-            };
-            hasher.update(id_bytes);
+            let id_bytes: [u8; 32] = DecodeHash::from(id)?;
+            id_bytes.to_vec()
         },
-        None => ()
+        None => vec![]
+    };
+
+    Ok(base64_url::encode(&compute_hash_chain(&prev_bytes, &id_bytes)))
+}
+
+/*
+    the hash chain at nonce 0, the seed domain is hashed
+    ahead of the process id. an empty domain gives the
+    same chain as gen_hash_chain(process_id, None)
+*/
+pub fn gen_genesis_hash_chain(process_id: &str, seed_domain: &str) -> Result<String, String> {
+    let process_bytes: [u8; 32] = DecodeHash::from(process_id)?;
+    Ok(base64_url::encode(&compute_hash_chain(&process_bytes, seed_domain.as_bytes())))
+}
+
+/*
+    a root committing to every hash chain of an epoch,
+    the sha256 of the decoded hash chains in nonce order
+*/
+pub fn gen_epoch_root(hash_chains: &[String]) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+
+    for hash_chain in hash_chains {
+        let hash_chain_bytes: [u8; 32] = DecodeHash::from(hash_chain)?;
+        hasher.update(hash_chain_bytes);
+    }
+
+    Ok(base64_url::encode(&hasher.finalize()))
+}
+
+// open epochs past this are dropped before another is added, they are rebuilt from the log
+const EPOCH_ROOT_CACHE_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+struct OpenEpochRoot {
+    hasher: Sha256,
+    first_nonce: i32,
+    last_nonce: i32
+}
+
+/*
+    the running root of open epochs by process and
+    epoch, so a read of an open epoch only hashes the
+    chains scheduled since the last one rather than
+    every chain of the epoch again
+*/
+#[derive(Default)]
+pub struct EpochRootCache {
+    roots: std::sync::Mutex<HashMap<(String, i32), OpenEpochRoot>>
+}
+
+impl EpochRootCache {
+    // the last nonce the cached root of the epoch covers
+    pub fn last_nonce(&self, process_id: &str, epoch: i32) -> Option<i32> {
+        let roots = self.roots.lock().unwrap();
+        roots.get(&(process_id.to_string(), epoch)).map(|root| root.last_nonce)
     }
 
-    hasher.update(prev_bytes);
+    /*
+        extend the cached root of the epoch with the chains
+        following its last nonce, chains it already covers
+        are skipped. None when the epoch has no chains
+    */
+    pub fn extend(&self, process_id: &str, epoch: i32, hash_chains: &[(i32, String)]) -> Result<Option<EpochCheckpoint>, String> {
+        let key = (process_id.to_string(), epoch);
+        let mut roots = self.roots.lock().unwrap();
+        let mut root = roots.get(&key).cloned();
+
+        for (nonce, hash_chain) in hash_chains {
+            let hash_chain_bytes: [u8; 32] = DecodeHash::from(hash_chain)?;
+            match root.as_mut() {
+                Some(root) if *nonce <= root.last_nonce => continue,
+                Some(root) => {
+                    root.hasher.update(hash_chain_bytes);
+                    root.last_nonce = *nonce;
+                },
+                None => {
+                    let mut hasher = Sha256::new();
+                    hasher.update(hash_chain_bytes);
+                    root = Some(OpenEpochRoot { hasher, first_nonce: *nonce, last_nonce: *nonce });
+                }
+            }
+        }
+
+        let root = match root {
+            Some(root) => root,
+            None => return Ok(None)
+        };
+        let checkpoint = EpochCheckpoint {
+            process_id: process_id.to_string(),
+            epoch,
+            root: base64_url::encode(&root.hasher.clone().finalize()),
+            first_nonce: root.first_nonce,
+            last_nonce: root.last_nonce,
+            timestamp: 0
+        };
 
-    let result = hasher.finalize();
+        if roots.len() >= EPOCH_ROOT_CACHE_CAPACITY && !roots.contains_key(&key) {
+            roots.clear();
+        }
+        roots.insert(key, root);
+        Ok(Some(checkpoint))
+    }
 
-    Ok(base64_url::encode(&result))
+    // the epoch closed, its checkpoint is read from the store from now on
+    pub fn remove(&self, process_id: &str, epoch: i32) {
+        self.roots.lock().unwrap().remove(&(process_id.to_string(), epoch));
+    }
 }
 
 /*
@@ -146,15 +292,19 @@ async fn fetch_values(deps: Arc<SchedulerDeps>, process_id: &String) -> Result<(
     let millis: i64 = duration.as_secs() as i64 * 1000 + i64::from(duration.subsec_millis());
 
     let latest_message = match deps.data_store
-        .get_latest_message(process_id) {
+        .get_latest_message(process_id).await {
             Ok(m) => m,
             Err(e) => return Err(format!("{:?}", e)),
         };
 
     match latest_message {
         Some(previous_message) => {
-            let epoch = previous_message.epoch;
+            let mut epoch = previous_message.epoch;
             let nonce = previous_message.nonce + 1;
+            let epoch_length = deps.config.epoch_length();
+            if epoch_length > 0 && nonce % epoch_length == 0 {
+                epoch = advance_epoch(&deps, process_id, epoch, millis).await?;
+            }
             let hash_chain = gen_hash_chain(
                 &previous_message.hash_chain, 
                 Some(&previous_message.message.id)
@@ -162,12 +312,40 @@ async fn fetch_values(deps: Arc<SchedulerDeps>, process_id: &String) -> Result<(
             Ok((epoch, nonce, hash_chain, millis))
         },
         None => {
-            let hash_chain = gen_hash_chain(&process_id, None)?;
+            let hash_chain = gen_genesis_hash_chain(&process_id, &deps.config.hash_chain_seed_domain())?;
             Ok((0, 0, hash_chain, millis))
         }
     }
 }
 
+/*
+    close the current epoch, its root is checkpointed
+    so it never has to be recomputed from the log
+*/
+async fn advance_epoch(deps: &SchedulerDeps, process_id: &str, epoch: i32, timestamp: i64) -> Result<i32, String> {
+    let hash_chains = deps.data_store
+        .get_epoch_hash_chains(process_id, epoch, None).await
+        .map_err(|e| format!("{:?}", e))?;
+
+    if let (Some(first), Some(last)) = (hash_chains.first(), hash_chains.last()) {
+        let chains: Vec<String> = hash_chains.iter().map(|(_, hash_chain)| hash_chain.clone()).collect();
+        let checkpoint = EpochCheckpoint {
+            process_id: process_id.to_string(),
+            epoch,
+            root: gen_epoch_root(&chains)?,
+            first_nonce: first.0,
+            last_nonce: last.0,
+            timestamp
+        };
+        deps.data_store
+            .save_epoch_checkpoint(&checkpoint).await
+            .map_err(|e| format!("{:?}", e))?;
+        deps.logger.log(format!("checkpointed epoch {} of process {}", epoch, process_id));
+    }
+
+    Ok(epoch + 1)
+}
+
 impl ScheduleProvider for ScheduleInfo {
     fn epoch(&self) -> String {
         self.epoch.to_string()
@@ -185,3 +363,34 @@ impl ScheduleProvider for ScheduleInfo {
         self.hash_chain.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_hash_chain() {
+        let hash_chain = compute_hash_chain(&[1; 32], &[2; 32]);
+        assert_eq!(hex::encode(&hash_chain), "c57d4f59c961b13e406cd991b0f342ec79e571dc2c1415ff72c6550645a3b198");
+
+        let generated = gen_hash_chain(&base64_url::encode(&[1; 32]), Some(&base64_url::encode(&[2; 32]))).unwrap();
+        assert_eq!(generated, base64_url::encode(&hash_chain));
+
+        // the genesis hash chain hashes the process id alone
+        let genesis = gen_hash_chain(&base64_url::encode(&[1; 32]), None).unwrap();
+        assert_eq!(genesis, "cs1uhCLEB_ttCYaQ8RMLfe1-wvf14dML2dUh8BU2N5M");
+        assert_eq!(genesis, base64_url::encode(&compute_hash_chain(&[1; 32], &[])));
+    }
+
+    #[test]
+    fn test_genesis_hash_chain_seed_domain() {
+        let process_id = base64_url::encode(&[1; 32]);
+        assert_eq!(gen_genesis_hash_chain(&process_id, "").unwrap(), gen_hash_chain(&process_id, None).unwrap());
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"ao-testnet");
+        hasher.update([1; 32]);
+        let expected = base64_url::encode(&hasher.finalize());
+        assert_eq!(gen_genesis_hash_chain(&process_id, "ao-testnet").unwrap(), expected);
+    }
+}
@@ -1,16 +1,88 @@
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use bundlr_sdk::{tags::Tag, Ed25519Signer, Secp256k1Signer, Verifier as _};
+use bytes::Bytes;
+use rsa::{BigUint, PaddingScheme, PublicKey, RsaPublicKey, rand_core::OsRng};
+use sha2::{Digest, Sha256};
+
+use super::builder::scheduled_process_id;
 use super::bytes::DataItem;
-use super::dal::Gateway;
+use super::dal::{Gateway, Config, Log, DataStore, TagOrderMode, ShapeCheckMode, VerificationMode};
 
 pub struct Verifier {
-    gateway: Arc<dyn Gateway>
+    gateway: Arc<dyn Gateway>,
+    config: Arc<dyn Config>,
+    logger: Arc<dyn Log>,
+    mode: VerificationMode,
+    // only needed to look up anchors already used
+    data_store: Option<Arc<dyn DataStore>>,
+    head_cache: Arc<HeadCache>,
+    verification_cache: Arc<VerificationCache>
+}
+
+// entries past this are pruned of expired results before another is added
+const TTL_CACHE_CAPACITY: usize = 1024;
+
+/*
+    results by key for a ttl that may depend on the
+    result, shared between verifiers through Deps as
+    a verifier only lives for one write
+*/
+pub struct TtlCache<V> {
+    entries: Mutex<HashMap<String, (Instant, V)>>
+}
+
+impl<V> Default for TtlCache<V> {
+    fn default() -> Self {
+        TtlCache { entries: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<V: Clone> TtlCache<V> {
+    // a cached result still within the ttl of its kind
+    pub fn get(&self, key: &str, ttl: impl Fn(&V) -> Duration) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        let (cached_at, value) = entries.get(key)?;
+        match cached_at.elapsed() < ttl(value) {
+            true => Some(value.clone()),
+            false => None
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: V, ttl: impl Fn(&V) -> Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= TTL_CACHE_CAPACITY {
+            entries.retain(|_, (cached_at, value)| cached_at.elapsed() < ttl(value));
+        }
+        if entries.len() >= TTL_CACHE_CAPACITY {
+            entries.clear();
+        }
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
 }
 
+// check_head results by tx id, so repeated ao-load references do not each go to the gateway
+pub type HeadCache = TtlCache<bool>;
+
+/*
+    content hashes of the full bytes of items that
+    passed verification, so a write of the exact
+    bytes a preview just checked is not checked again
+*/
+pub type VerificationCache = TtlCache<()>;
+
 #[derive(Debug)]
 pub enum VerifyErrorType {
     VerifyError(String),
+    SuspiciousShape(String),
+    SignatureTypeNotAllowed(u16),
+    // the owner already sent a message to the process with this anchor
+    AnchorReused(String),
+    // the item carries more tags, or more tag bytes, than the limit it names
+    TagLimitExceeded(String),
 }
 
 impl From<reqwest::Error> for VerifyErrorType {
@@ -25,35 +97,343 @@ impl From<String> for VerifyErrorType {
     }
 }
 
+// arweave keys all use the standard public exponent
+const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+/*
+    check an arweave RSA-PSS signature over message,
+    pub_key is the modulus as the su advertises it
+*/
+pub fn verify_signature(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyErrorType> {
+    let key = RsaPublicKey::new(BigUint::from_bytes_be(pub_key), BigUint::from(RSA_PUBLIC_EXPONENT))
+        .map_err(|e| VerifyErrorType::VerifyError(format!("Invalid public key: {}", e)))?;
+    let hashed = Sha256::digest(message);
+    key.verify(PaddingScheme::new_pss::<Sha256, _>(OsRng), &hashed, signature)
+        .map_err(|_| VerifyErrorType::VerifyError("Invalid signature".to_string()))
+}
+
+/*
+    check an ethereum (type 3) signature, a recoverable
+    secp256k1 signature over the eip-191 hash of message
+    that must recover to the address of pub_key
+*/
+pub fn verify_ethereum_signature(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyErrorType> {
+    if signature.len() != 65 {
+        return Err(VerifyErrorType::VerifyError(format!("Invalid signature length {}", signature.len())));
+    }
+    Secp256k1Signer::verify(Bytes::copy_from_slice(pub_key), Bytes::copy_from_slice(message), Bytes::copy_from_slice(signature))
+        .map_err(|_| VerifyErrorType::VerifyError("Invalid signature".to_string()))
+}
+
+// check an ed25519 (type 2) signature made directly over message
+pub fn verify_ed25519_signature(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyErrorType> {
+    Ed25519Signer::verify(Bytes::copy_from_slice(pub_key), Bytes::copy_from_slice(message), Bytes::copy_from_slice(signature))
+        .map_err(|_| VerifyErrorType::VerifyError("Invalid signature".to_string()))
+}
+
 impl Verifier {
-    pub fn new(gateway: Arc<dyn Gateway>) -> Self {
+    pub fn new(gateway: Arc<dyn Gateway>, config: Arc<dyn Config>, logger: Arc<dyn Log>, mode: VerificationMode) -> Self {
         Verifier {
-            gateway
+            gateway,
+            config,
+            logger,
+            mode,
+            data_store: None,
+            head_cache: Arc::default(),
+            verification_cache: Arc::default()
         }
     }
 
+    pub fn with_head_cache(mut self, head_cache: Arc<HeadCache>) -> Self {
+        self.head_cache = head_cache;
+        self
+    }
+
+    pub fn with_verification_cache(mut self, verification_cache: Arc<VerificationCache>) -> Self {
+        self.verification_cache = verification_cache;
+        self
+    }
+
+    pub fn with_data_store(mut self, data_store: Arc<dyn DataStore>) -> Self {
+        self.data_store = Some(data_store);
+        self
+    }
+
     pub async fn verify_data_item(&self, data_item: &DataItem) -> Result<(), VerifyErrorType>{
+        /*
+            the anchor check depends on what is stored by
+            now rather than on the bytes so it is never
+            taken from the cache
+        */
+        let ttl = Duration::from_secs(self.config.verification_cache_ttl_seconds());
+        let content_hash = match ttl.is_zero() {
+            true => None,
+            false => data_item.as_bytes().ok()
+                .map(|bytes| base64_url::encode(&Sha256::digest(bytes)))
+        };
+        if let Some(content_hash) = &content_hash {
+            if self.verification_cache.get(content_hash, |_| ttl).is_some() {
+                return self.check_anchor(data_item).await;
+            }
+        }
+
+        self.verify_content(data_item).await?;
+        if let Some(content_hash) = &content_hash {
+            self.verification_cache.insert(content_hash, (), |_| ttl);
+        }
+        self.check_anchor(data_item).await
+    }
+
+    // every check that depends only on the bytes of the item and its references
+    async fn verify_content(&self, data_item: &DataItem) -> Result<(), VerifyErrorType> {
+        self.check_signature_type(data_item)?;
+        self.check_signature(data_item)?;
+
         let tags = data_item.tags();
+        self.check_tag_limits(&tags)?;
 
-        // if this is a data attestation request the head on the gateway
-        if let Some(tag) = tags.iter().find(|tag| tag.name == "ao-load") {
-            let check = self.gateway.check_head(tag.value.clone()).await?;
+        // if this is a data attestation request the head on the gateway, trusted when lenient
+        if let Some(load) = data_item.tag("ao-load").filter(|_| self.mode == VerificationMode::Strict) {
+            let check = self.check_head(load).await?;
             if !check {
                 return Err(VerifyErrorType::VerifyError("Failed to verify ao-load value on the gateway".to_string()));
             } 
         }
 
+        if self.config.tag_order_mode() == TagOrderMode::Strict
+            && !tags_in_order(&tags, &self.config.canonical_tag_order()) {
+            return Err(VerifyErrorType::VerifyError("Data item tags are not in canonical order".to_string()));
+        }
+
+        self.check_variant(&tags)?;
+        self.check_shape(data_item)?;
+
         Ok(())
     }
+
+    // a negative result is cached for less time, the tx may just not be seen yet
+    async fn check_head(&self, tx_id: &str) -> Result<bool, VerifyErrorType> {
+        let positive_ttl = Duration::from_secs(self.config.check_head_ttl_seconds());
+        let negative_ttl = Duration::from_secs(self.config.check_head_negative_ttl_seconds());
+        let ttl = |found: &bool| if *found { positive_ttl } else { negative_ttl };
+        if let Some(found) = self.head_cache.get(tx_id, ttl) {
+            return Ok(found);
+        }
+        let found = self.gateway.check_head(tx_id.to_string()).await?;
+        self.head_cache.insert(tx_id, found, ttl);
+        Ok(found)
+    }
+
+    /*
+        runs before anything type specific so a deployment
+        can restrict which kinds of keys may write to it
+    */
+    fn check_signature_type(&self, data_item: &DataItem) -> Result<(), VerifyErrorType> {
+        let allowed = self.config.allowed_signature_types();
+        let signature_type = data_item.signature_type();
+        if allowed.is_empty() || allowed.contains(&signature_type) {
+            return Ok(());
+        }
+        Err(VerifyErrorType::SignatureTypeNotAllowed(signature_type))
+    }
+
+    // arweave, ed25519 and ethereum signatures are checked in either mode, other types are not
+    fn check_signature(&self, data_item: &DataItem) -> Result<(), VerifyErrorType> {
+        let verify = match data_item.signature_type() {
+            1 => verify_signature,
+            2 => verify_ed25519_signature,
+            3 => verify_ethereum_signature,
+            _ => return Ok(())
+        };
+        let owner = base64_url::decode(&data_item.owner())
+            .map_err(|e| VerifyErrorType::VerifyError(format!("Invalid owner: {:?}", e)))?;
+        let preimage = data_item.clone().get_message()
+            .map_err(|e| VerifyErrorType::VerifyError(format!("{:?}", e)))?;
+        verify(&owner, &preimage, &data_item.signature)
+    }
+
+    /*
+        an owner may use an anchor once per process, so
+        the same signed message cannot be scheduled twice
+    */
+    pub async fn check_anchor(&self, data_item: &DataItem) -> Result<(), VerifyErrorType> {
+        let data_store = match &self.data_store {
+            Some(data_store) if self.config.reject_reused_anchors() => data_store,
+            _ => return Ok(())
+        };
+        let anchor = data_item.anchor();
+        if anchor.is_empty() || data_item.tag("Type") == Some("Process") {
+            return Ok(());
+        }
+        // a retry of a stored message used the anchor itself, the write returns it as it was
+        if data_store.get_message(&data_item.id()).await.is_ok() {
+            return Ok(());
+        }
+
+        let process_id = scheduled_process_id(data_item);
+        let owner = data_item.owner_address();
+        let used = data_store.anchor_used(&process_id, &owner, &anchor).await
+            .map_err(|e| VerifyErrorType::VerifyError(format!("Failed to check anchor: {:?}", e)))?;
+        if used {
+            return Err(VerifyErrorType::AnchorReused(format!(
+                "Anchor {} was already used by {} in process {}", anchor, owner, process_id
+            )));
+        }
+        Ok(())
+    }
+
+    // arweave caps tags, past the cap they only bloat the bundle and the store
+    fn check_tag_limits(&self, tags: &[Tag]) -> Result<(), VerifyErrorType> {
+        let max_count = self.config.max_tag_count();
+        if tags.len() > max_count {
+            return Err(VerifyErrorType::TagLimitExceeded(format!(
+                "Data item has {} tags, more than MAX_TAG_COUNT {}", tags.len(), max_count
+            )));
+        }
+
+        let max_bytes = self.config.max_tag_bytes();
+        let tag_bytes: usize = tags.iter().map(|tag| tag.name.len() + tag.value.len()).sum();
+        if tag_bytes > max_bytes {
+            return Err(VerifyErrorType::TagLimitExceeded(format!(
+                "Data item has {} bytes of tags, more than MAX_TAG_BYTES {}", tag_bytes, max_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /*
+        keep incompatible protocol variants out of a
+        process by only accepting configured variants
+    */
+    fn check_variant(&self, tags: &[Tag]) -> Result<(), VerifyErrorType> {
+        let allowed = self.config.allowed_variants();
+        if allowed.is_empty() {
+            return Ok(());
+        }
+
+        match tags.iter().find(|tag| tag.name == "Variant") {
+            Some(tag) if allowed.contains(&tag.value) => Ok(()),
+            Some(tag) => Err(VerifyErrorType::VerifyError(format!("Unsupported Variant {}", tag.value))),
+            None => Err(VerifyErrorType::VerifyError("Variant tag not present".to_string()))
+        }
+    }
+
+    /*
+        flag items whose tag count is wildly out of
+        proportion to their data, lots of tags on a
+        tiny payload is a common abuse pattern
+    */
+    fn check_shape(&self, data_item: &DataItem) -> Result<(), VerifyErrorType> {
+        let mode = self.config.shape_check_mode();
+        if mode == ShapeCheckMode::Off {
+            return Ok(());
+        }
+
+        let tag_count = data_item.tags().len();
+        let data_len = data_item.data_bytes().len();
+        if !suspicious_shape(tag_count, data_len, self.config.shape_check_min_tags(), self.config.shape_check_min_bytes_per_tag()) {
+            return Ok(());
+        }
+
+        let message = format!(
+            "Data item {} has {} tags but only {} bytes of data",
+            data_item.id(), tag_count, data_len
+        );
+        match mode {
+            ShapeCheckMode::Reject => Err(VerifyErrorType::SuspiciousShape(message)),
+            _ => {
+                self.logger.error(message);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn suspicious_shape(tag_count: usize, data_len: usize, min_tags: usize, min_bytes_per_tag: usize) -> bool {
+    tag_count >= min_tags && data_len < tag_count.saturating_mul(min_bytes_per_tag)
+}
+
+/*
+    tags listed in the canonical order must appear in
+    that relative order, unlisted tags can go anywhere
+*/
+pub fn tags_in_order(tags: &[Tag], order: &[String]) -> bool {
+    let positions: Vec<usize> = tags.iter()
+        .filter_map(|tag| order.iter().position(|name| *name == tag.name))
+        .collect();
+    positions.windows(2).all(|pair| pair[0] <= pair[1])
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::core::bytes::SignerMap;
+    use crate::domain::core::builder::tests::signed_item;
     use crate::domain::core::dal::NetworkInfo;
     use async_trait::async_trait;
 
+    struct MockConfig {
+        tag_order_mode: TagOrderMode,
+        canonical_tag_order: Vec<String>,
+        shape_check_mode: ShapeCheckMode,
+        allowed_variants: Vec<String>,
+        allowed_signature_types: Vec<u16>
+    }
+
+    impl Config for MockConfig {
+        fn su_wallet_path(&self) -> String {
+            "wallet.json".to_string()
+        }
+        fn upload_node_url(&self) -> String {
+            "https://up.example".to_string()
+        }
+        fn gateway_url(&self) -> String {
+            "https://gateway.example".to_string()
+        }
+        fn mode(&self) -> String {
+            "su".to_string()
+        }
+        fn scheduler_list_path(&self) -> String {
+            "".to_string()
+        }
+        fn tag_order_mode(&self) -> TagOrderMode {
+            self.tag_order_mode
+        }
+        fn canonical_tag_order(&self) -> Vec<String> {
+            self.canonical_tag_order.clone()
+        }
+        fn shape_check_mode(&self) -> ShapeCheckMode {
+            self.shape_check_mode
+        }
+        fn allowed_variants(&self) -> Vec<String> {
+            self.allowed_variants.clone()
+        }
+        fn allowed_signature_types(&self) -> Vec<u16> {
+            self.allowed_signature_types.clone()
+        }
+    }
+
+    fn mock_config() -> Arc<MockConfig> {
+        Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::None,
+            canonical_tag_order: vec![],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec![],
+            allowed_signature_types: vec![]
+        })
+    }
+
+    struct MockLogger;
+    impl Log for MockLogger {
+        fn log(&self, message: String) {
+            println!("{}", message)
+        }
+        fn error(&self, message: String) {
+            println!("{}", message);
+        }
+    }
+
     struct MockGateway;
     
     #[async_trait]
@@ -68,6 +448,17 @@ mod tests {
                 current: "current".to_string()
             })
         }
+
+        async fn network_info_from(&self, _gateway_url: &str) -> Result<NetworkInfo, String> {
+            self.network_info().await
+        }
+
+        async fn block_timestamp(&self, _height: i64) -> Result<i64, String> {
+            Err("not implemented".to_string())
+        }
+        async fn tx_tags(&self, _tx_id: &str) -> Result<Vec<Tag>, String> {
+            Err("not implemented".to_string())
+        }
     }
     
     #[tokio::test]
@@ -75,7 +466,7 @@ mod tests {
         let item_bytes = base64_url::decode(&"AQB9q2yhsQlBHv2LOTIrtmKjw063S1DG0prKcq86DykIegmPnXOReXkWXwpqXt4YxTRw6Rw1jG7f1QFF5ReoJO2MrJmia9ymkTmnhamv3lsYYIotBC6U4Bmzo6IZiKmn2llJt0MDvCe8rxzG15vvff9bpnDIVflY_Dm9Y0dCH-w2Xg8rb2xLq-cM8SBoNRiYruwcwpahiHTjXcxboJKksZRXaI_E7_7vL1gWlMLqeYeF_uXqkth8_PGtZcqMA7pbTYcRzGki_rifGXKUIZKgSIRXTk54iboiqNzOklIFpDKDJpC9Xk_6ppSw_Xzs8S0KpR-veBL8TeURtGhrsDecu_36Pk2MMvdZedxiAg7bvQ9H_NZecoZcju-sQKZiE7haq9Nos3g6njh9IpXivGJ1k8tRLeox7hXOeynffzcXz1Vnz5c4Zxw8LKUbLygni49sflKyFTMnQ8sgDw00fPsuhrznq37-2OLhmYe-tIg-TEV3T4VNdqchzeRSFIv_l7ZJcxeFxcEgdq9aXMx2yzVhSInFuk_W8fJSbhPKX9cewbr4BA_XUNMReowLVcnjB_19iCWnivkVk9sz-QRbjuVL2IMqZePWcRdN5ncXRJoYv4F-Z4FfXDCFuyCD4UAtiQfdch-S4KvRf99DwKrZrMIF28MDdRFdE3ZGDs3FXcPuN8eMLoKBrkyfkM3J89W1GNvrcCNHSNzhF8oPItU4Qno7-x52ZIOAjfdFcXTYLQYU7Xfr6GKaRByemPrkbkrJpdB8RQREt3rQRDNGRQ0jnbPn62PQugvss98JZn9D4ScNusbbgKMihj4MqfXE2mt7Ab9ewx5d01d-Mwf3D6mGz_ERBJgJo8b119bRXdNvgUDJC58NFd4chEOUF4mbyj2pZB9P7fx22yEvV7y6DNzuKvk02YQt7TwL7sdxH1PT63CYJx0tlVGGDvJhGKUQwOfDaXHFMjuuUlXa_klTJT5wEb78aAyh33rw0n9wpOakTIk2KgekbJAzVWCT0BfLrrOhKs3556_d--2mLmcLOONosBjSLokuvtyrTOX7btKRf6Zl5l3wtxsFaPgO6M3Qy9UR46AtK76XSFQd9kcDf_Qj1FyronJS_enQFWYn5Um97mDnYT9SJwMpDFS_FYBTKlsNhsVy11EW5kKuo6mTRlfebJa9CQv-NzbUajd7ulAcM4VNWYt-KbbhVZtUUUxgDvXJdlwRSYR5U8JwSze3sfatb5mbds-EAS-tT7grwrvTb4wRz20e9ARtBg6kC_x8QujHmFORJ97zrFlnnunPbsWgwWz8bfT9RMFy5xUE1KDCtnJqp-M3FoWwQc4sREIyCl7Q6JTq_slPe-Xwt9C5oquj4e_SoOuTAfqDPAmIG6rEXKSN7RP3KRjN5IA5Wpp2I0hgOJ6bT2qNAAUAAAAAAAAASAAAAAAAAAAKGkRhdGEtUHJvdG9jb2wEYW8QZnVuY3Rpb24GcmF3GkRhdGEtUHJvdG9jb2wEYW8OYW8tdHlwZQ5tZXNzYWdlBlNESwRhbwA2NTgz".to_string()).expect("failed to encode data item");
         let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
         let gateway = Arc::new(MockGateway);
-        let verifier = Verifier::new(gateway);
+        let verifier = Verifier::new(gateway, mock_config(), Arc::new(MockLogger), VerificationMode::Strict);
         let result = verifier.verify_data_item(&data_item).await;
         assert!(result.is_ok(), "Verification failed");
     }
@@ -87,7 +478,7 @@ mod tests {
         let item_bytes = base64_url::decode(&"AQBkY76kTNuBWFqOarur046vzChXoo34tYbUwFFlN4LzTvf6N5DrPy4vKXqIeg9cSe2rxK7MtChI2z8t8-Vp97jwLoOcXpErEsdlx2jHdB9xi38NsCuD_BEWD2jYFDI1F79yGIbLDgUJ-0U-LLV0lr1r52nzKtdY7Q9AfsKb6FNrUdYD27ybbSDDyg5ZnTNBA-Y_dXPjIvkhWQkwM_BEX_A2Q28t4etvL6czZS7sD6EY8TYjNxKva3XpP6hWuUVdnjlwnHbZn3E_1R4Xb7doL7cLEM7CcqCfo5tm5OYEwPXBtpEAZs528yCQsT295aKgdr7kA9blTWrB_MWlRNOpDYxXwo0iAtvwbZKuOwVtbMfFJMv1RN1itUHVcRcXIjtgutKZOkiLVT8_I8NCpzjSe-U-VsnxVYYng6H6EB7cYeRb9KHYGVp_ZVnnkoUAg6IAo7unJzbqnb3brMb5FiX9Ub0hY1eW47dqv9DH_Kft6nbBffKIB0TOhKQpKhdjGkg5STn6x03TeEL3RZLcw14b43gqrkoj_j8BVbAyry4F1UiHAovViZlYHR_opTRG0Fu_RKrVEgQxZjUXtlsT4QckNBJWTMB365l2Sz61wIRR0EV1cnL2REU-KEEGkmtRhEXW5sA3P_YDnv_P9SpcXviyAe0kLChCOYiRJmq9WQ8E90fKoIKBrkyfkM3J89W1GNvrcCNHSNzhF8oPItU4Qno7-x52ZIOAjfdFcXTYLQYU7Xfr6GKaRByemPrkbkrJpdB8RQREt3rQRDNGRQ0jnbPn62PQugvss98JZn9D4ScNusbbgKMihj4MqfXE2mt7Ab9ewx5d01d-Mwf3D6mGz_ERBJgJo8b119bRXdNvgUDJC58NFd4chEOUF4mbyj2pZB9P7fx22yEvV7y6DNzuKvk02YQt7TwL7sdxH1PT63CYJx0tlVGGDvJhGKUQwOfDaXHFMjuuUlXa_klTJT5wEb78aAyh33rw0n9wpOakTIk2KgekbJAzVWCT0BfLrrOhKs3556_d--2mLmcLOONosBjSLokuvtyrTOX7btKRf6Zl5l3wtxsFaPgO6M3Qy9UR46AtK76XSFQd9kcDf_Qj1FyronJS_enQFWYn5Um97mDnYT9SJwMpDFS_FYBTKlsNhsVy11EW5kKuo6mTRlfebJa9CQv-NzbUajd7ulAcM4VNWYt-KbbhVZtUUUxgDvXJdlwRSYR5U8JwSze3sfatb5mbds-EAS-tT7grwrvTb4wRz20e9ARtBg6kC_x8QujHmFORJ97zrFlnnunPbsWgwWz8bfT9RMFy5xUE1KDCtnJqp-M3FoWwQc4sREIyCl7Q6JTq_slPe-Xwt9C5oquj4e_SoOuTAfqDPAmIG6rEXKSN7RP3KRjN5IA5Wpp2I0hgOJ6bT2qNAAYAAAAAAAAAYQAAAAAAAAAMGkRhdGEtUHJvdG9jb2wEYW8QZnVuY3Rpb24GcmF3DmFvLWxvYWQgYXNkZmFzZGZhc2RmYXNkZhpEYXRhLVByb3RvY29sBGFvDmFvLXR5cGUObWVzc2FnZQZTREsEYW8ANDQzOA".to_string()).expect("failed to encode data item");
         let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
         let gateway = Arc::new(MockGateway);
-        let verifier = Verifier::new(gateway);
+        let verifier = Verifier::new(gateway, mock_config(), Arc::new(MockLogger), VerificationMode::Strict);
         let result = verifier.verify_data_item(&data_item).await;
         assert!(result.is_ok(), "Verification failed");
     }
@@ -106,6 +497,98 @@ mod tests {
                 current: "current".to_string()
             })
         }
+
+        async fn network_info_from(&self, _gateway_url: &str) -> Result<NetworkInfo, String> {
+            self.network_info().await
+        }
+
+        async fn block_timestamp(&self, _height: i64) -> Result<i64, String> {
+            Err("not implemented".to_string())
+        }
+        async fn tx_tags(&self, _tx_id: &str) -> Result<Vec<Tag>, String> {
+            Err("not implemented".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_modes() {
+        let item = variant_item(vec![Tag::new("ao-load", "missing-tx")]);
+        let verifier = |mode| Verifier::new(Arc::new(MockGatewayFail), mock_config(), Arc::new(MockLogger), mode);
+
+        // only strict asks the gateway, which does not have the tx
+        assert!(verifier(VerificationMode::Strict).verify_data_item(&item).await.is_err());
+        assert!(verifier(VerificationMode::Lenient).verify_data_item(&item).await.is_ok());
+
+        // checks that need no round trip still run when lenient
+        let result = verifier(VerificationMode::Lenient).verify_data_item(&shaped_item(129, 10)).await;
+        assert!(matches!(result, Err(VerifyErrorType::TagLimitExceeded(_))));
+    }
+
+    // counts check_head calls, found is what each call returns
+    struct CountingGateway {
+        found: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Gateway for CountingGateway {
+        async fn check_head(&self, _tag_value: String) -> Result<bool, String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(self.found)
+        }
+
+        async fn network_info(&self) -> Result<NetworkInfo, String> {
+            MockGateway.network_info().await
+        }
+
+        async fn network_info_from(&self, _gateway_url: &str) -> Result<NetworkInfo, String> {
+            self.network_info().await
+        }
+
+        async fn block_timestamp(&self, _height: i64) -> Result<i64, String> {
+            Err("not implemented".to_string())
+        }
+        async fn tx_tags(&self, _tx_id: &str) -> Result<Vec<Tag>, String> {
+            Err("not implemented".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_caches_check_head() {
+        let item = variant_item(vec![Tag::new("ao-load", "load-tx")]);
+        let head_cache = Arc::new(HeadCache::default());
+
+        let found = Arc::new(CountingGateway { found: true, calls: Default::default() });
+        for _ in 0..3 {
+            // a fresh verifier per write, as flows builds one, sharing the cache
+            let verifier = Verifier::new(found.clone(), mock_config(), Arc::new(MockLogger), VerificationMode::Strict)
+                .with_head_cache(head_cache.clone());
+            assert!(verifier.verify_data_item(&item).await.is_ok());
+        }
+        assert_eq!(found.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let missing_item = variant_item(vec![Tag::new("ao-load", "missing-tx")]);
+        let missing = Arc::new(CountingGateway { found: false, calls: Default::default() });
+        let verifier = Verifier::new(missing.clone(), mock_config(), Arc::new(MockLogger), VerificationMode::Strict)
+            .with_head_cache(head_cache);
+        assert!(verifier.verify_data_item(&missing_item).await.is_err());
+        assert!(verifier.verify_data_item(&missing_item).await.is_err());
+        assert_eq!(missing.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_head_cache_negative_ttl() {
+        let cache = HeadCache::default();
+        let ttl = |found: &bool| if *found { Duration::from_secs(60) } else { Duration::from_millis(10) };
+        cache.insert("found", true, ttl);
+        cache.insert("missing", false, ttl);
+        assert_eq!(cache.get("found", ttl), Some(true));
+        assert_eq!(cache.get("missing", ttl), Some(false));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get("found", ttl), Some(true));
+        assert_eq!(cache.get("missing", ttl), None);
+        assert_eq!(cache.get("unknown", ttl), None);
     }
 
     #[tokio::test]
@@ -115,9 +598,246 @@ mod tests {
         let item_bytes = base64_url::decode(&"AQBkY76kTNuBWFqOarur046vzChXoo34tYbUwFFlN4LzTvf6N5DrPy4vKXqIeg9cSe2rxK7MtChI2z8t8-Vp97jwLoOcXpErEsdlx2jHdB9xi38NsCuD_BEWD2jYFDI1F79yGIbLDgUJ-0U-LLV0lr1r52nzKtdY7Q9AfsKb6FNrUdYD27ybbSDDyg5ZnTNBA-Y_dXPjIvkhWQkwM_BEX_A2Q28t4etvL6czZS7sD6EY8TYjNxKva3XpP6hWuUVdnjlwnHbZn3E_1R4Xb7doL7cLEM7CcqCfo5tm5OYEwPXBtpEAZs528yCQsT295aKgdr7kA9blTWrB_MWlRNOpDYxXwo0iAtvwbZKuOwVtbMfFJMv1RN1itUHVcRcXIjtgutKZOkiLVT8_I8NCpzjSe-U-VsnxVYYng6H6EB7cYeRb9KHYGVp_ZVnnkoUAg6IAo7unJzbqnb3brMb5FiX9Ub0hY1eW47dqv9DH_Kft6nbBffKIB0TOhKQpKhdjGkg5STn6x03TeEL3RZLcw14b43gqrkoj_j8BVbAyry4F1UiHAovViZlYHR_opTRG0Fu_RKrVEgQxZjUXtlsT4QckNBJWTMB365l2Sz61wIRR0EV1cnL2REU-KEEGkmtRhEXW5sA3P_YDnv_P9SpcXviyAe0kLChCOYiRJmq9WQ8E90fKoIKBrkyfkM3J89W1GNvrcCNHSNzhF8oPItU4Qno7-x52ZIOAjfdFcXTYLQYU7Xfr6GKaRByemPrkbkrJpdB8RQREt3rQRDNGRQ0jnbPn62PQugvss98JZn9D4ScNusbbgKMihj4MqfXE2mt7Ab9ewx5d01d-Mwf3D6mGz_ERBJgJo8b119bRXdNvgUDJC58NFd4chEOUF4mbyj2pZB9P7fx22yEvV7y6DNzuKvk02YQt7TwL7sdxH1PT63CYJx0tlVGGDvJhGKUQwOfDaXHFMjuuUlXa_klTJT5wEb78aAyh33rw0n9wpOakTIk2KgekbJAzVWCT0BfLrrOhKs3556_d--2mLmcLOONosBjSLokuvtyrTOX7btKRf6Zl5l3wtxsFaPgO6M3Qy9UR46AtK76XSFQd9kcDf_Qj1FyronJS_enQFWYn5Um97mDnYT9SJwMpDFS_FYBTKlsNhsVy11EW5kKuo6mTRlfebJa9CQv-NzbUajd7ulAcM4VNWYt-KbbhVZtUUUxgDvXJdlwRSYR5U8JwSze3sfatb5mbds-EAS-tT7grwrvTb4wRz20e9ARtBg6kC_x8QujHmFORJ97zrFlnnunPbsWgwWz8bfT9RMFy5xUE1KDCtnJqp-M3FoWwQc4sREIyCl7Q6JTq_slPe-Xwt9C5oquj4e_SoOuTAfqDPAmIG6rEXKSN7RP3KRjN5IA5Wpp2I0hgOJ6bT2qNAAYAAAAAAAAAYQAAAAAAAAAMGkRhdGEtUHJvdG9jb2wEYW8QZnVuY3Rpb24GcmF3DmFvLWxvYWQgYXNkZmFzZGZhc2RmYXNkZhpEYXRhLVByb3RvY29sBGFvDmFvLXR5cGUObWVzc2FnZQZTREsEYW8ANDQzOA".to_string()).expect("failed to encode data item");
         let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
         let gateway = Arc::new(MockGatewayFail);
-        let verifier = Verifier::new(gateway);
+        let verifier = Verifier::new(gateway, mock_config(), Arc::new(MockLogger), VerificationMode::Strict);
         let result = verifier.verify_data_item(&data_item).await;
         assert!(result.is_err(), "Verification failed");
     }
-}
 
+    // the secp256k1 key 1, its address is a well known test vector
+    const TEST_ETHEREUM_KEY: &str = "1111111111111111111111111111111JEKNVnkbo3jma5nREBBJCDoXFVeKkD56V3xKrvRmWxFH";
+
+    // the ed25519 key of the bundlr sdk signer tests
+    const TEST_ED25519_KEY: &str = "kNykCXNxgePDjFbDWjPNvXQRa8U12Ywc19dFVaQ7tebUj3m7H4sF4KKdJwM7yxxb3rqxchdjezX9Szh8bLcQAjb";
+
+    // an item of signature_type without tags, its signature is zeroed
+    fn unsigned_item(signature_type: u16, owner: &[u8], data: &[u8]) -> DataItem {
+        let mut bytes = signature_type.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&vec![0; SignerMap::from(signature_type).get_config().sig_length]);
+        bytes.extend_from_slice(owner);
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(data);
+        DataItem::from_bytes(bytes).expect("failed to build data item")
+    }
+
+    #[tokio::test]
+    async fn test_verify_ethereum_signature() {
+        use bundlr_sdk::Signer as _;
+        let signer = Secp256k1Signer::from_base58(TEST_ETHEREUM_KEY).expect("invalid test key");
+        let mut item = unsigned_item(3, &signer.pub_key(), b"data");
+        let message = item.get_message().unwrap();
+        item.signature = signer.sign(message).unwrap().to_vec();
+        let item = DataItem::from_bytes(item.as_bytes().unwrap()).expect("failed to parse signed item");
+
+        let verifier = Verifier::new(Arc::new(MockGateway), mock_config(), Arc::new(MockLogger), VerificationMode::Strict);
+        assert_eq!(item.signature_type(), 3);
+        assert_eq!(item.owner_address(), "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+        assert_eq!(item.id(), base64_url::encode(&Sha256::digest(&item.signature)));
+        assert!(verifier.verify_data_item(&item).await.is_ok());
+
+        // a signature made over other data
+        let mut forged = unsigned_item(3, &signer.pub_key(), b"other");
+        forged.signature = item.signature.clone();
+        match verifier.verify_data_item(&forged).await {
+            Err(VerifyErrorType::VerifyError(e)) => assert_eq!(e, "Invalid signature"),
+            _ => panic!("expected a forged ethereum signature to be rejected")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_ed25519_signature() {
+        use bundlr_sdk::Signer as _;
+        let signer = Ed25519Signer::from_base58(TEST_ED25519_KEY).expect("invalid test key");
+        let mut item = unsigned_item(2, &signer.pub_key(), b"data");
+        let message = item.get_message().unwrap();
+        item.signature = signer.sign(message).unwrap().to_vec();
+        let bytes = item.as_bytes().unwrap();
+        // 64 byte signature and 32 byte owner, not the 512 bytes of each an rsa item has
+        assert_eq!(bytes.len(), 2 + 64 + 32 + 2 + 16 + 4);
+        let item = DataItem::from_bytes(bytes).expect("failed to parse signed item");
+
+        let verifier = Verifier::new(Arc::new(MockGateway), mock_config(), Arc::new(MockLogger), VerificationMode::Strict);
+        assert_eq!(item.signature_type(), 2);
+        assert_eq!(item.signature_length(), 64);
+        assert_eq!(item.data_bytes(), b"data");
+        assert!(verifier.verify_data_item(&item).await.is_ok());
+
+        let mut forged = unsigned_item(2, &signer.pub_key(), b"other");
+        forged.signature = item.signature.clone();
+        match verifier.verify_data_item(&forged).await {
+            Err(VerifyErrorType::VerifyError(e)) => assert_eq!(e, "Invalid signature"),
+            _ => panic!("expected a forged ed25519 signature to be rejected")
+        }
+    }
+
+    #[test]
+    fn test_tags_in_order() {
+        let order = vec!["Data-Protocol".to_string(), "Type".to_string()];
+        let ordered = vec![
+            Tag::new("Data-Protocol", "ao"),
+            Tag::new("Variant", "ao.TN.1"),
+            Tag::new("Type", "Message"),
+        ];
+        let unordered = vec![
+            Tag::new("Type", "Message"),
+            Tag::new("Data-Protocol", "ao"),
+        ];
+        assert!(tags_in_order(&ordered, &order));
+        assert!(!tags_in_order(&unordered, &order));
+        assert!(tags_in_order(&unordered, &[]));
+    }
+
+    #[tokio::test]
+    async fn test_verify_strict_tag_order() {
+        // the fixture item has tags Data-Protocol, function, Data-Protocol, ao-type, SDK
+        let item_bytes = base64_url::decode(&"AQB9q2yhsQlBHv2LOTIrtmKjw063S1DG0prKcq86DykIegmPnXOReXkWXwpqXt4YxTRw6Rw1jG7f1QFF5ReoJO2MrJmia9ymkTmnhamv3lsYYIotBC6U4Bmzo6IZiKmn2llJt0MDvCe8rxzG15vvff9bpnDIVflY_Dm9Y0dCH-w2Xg8rb2xLq-cM8SBoNRiYruwcwpahiHTjXcxboJKksZRXaI_E7_7vL1gWlMLqeYeF_uXqkth8_PGtZcqMA7pbTYcRzGki_rifGXKUIZKgSIRXTk54iboiqNzOklIFpDKDJpC9Xk_6ppSw_Xzs8S0KpR-veBL8TeURtGhrsDecu_36Pk2MMvdZedxiAg7bvQ9H_NZecoZcju-sQKZiE7haq9Nos3g6njh9IpXivGJ1k8tRLeox7hXOeynffzcXz1Vnz5c4Zxw8LKUbLygni49sflKyFTMnQ8sgDw00fPsuhrznq37-2OLhmYe-tIg-TEV3T4VNdqchzeRSFIv_l7ZJcxeFxcEgdq9aXMx2yzVhSInFuk_W8fJSbhPKX9cewbr4BA_XUNMReowLVcnjB_19iCWnivkVk9sz-QRbjuVL2IMqZePWcRdN5ncXRJoYv4F-Z4FfXDCFuyCD4UAtiQfdch-S4KvRf99DwKrZrMIF28MDdRFdE3ZGDs3FXcPuN8eMLoKBrkyfkM3J89W1GNvrcCNHSNzhF8oPItU4Qno7-x52ZIOAjfdFcXTYLQYU7Xfr6GKaRByemPrkbkrJpdB8RQREt3rQRDNGRQ0jnbPn62PQugvss98JZn9D4ScNusbbgKMihj4MqfXE2mt7Ab9ewx5d01d-Mwf3D6mGz_ERBJgJo8b119bRXdNvgUDJC58NFd4chEOUF4mbyj2pZB9P7fx22yEvV7y6DNzuKvk02YQt7TwL7sdxH1PT63CYJx0tlVGGDvJhGKUQwOfDaXHFMjuuUlXa_klTJT5wEb78aAyh33rw0n9wpOakTIk2KgekbJAzVWCT0BfLrrOhKs3556_d--2mLmcLOONosBjSLokuvtyrTOX7btKRf6Zl5l3wtxsFaPgO6M3Qy9UR46AtK76XSFQd9kcDf_Qj1FyronJS_enQFWYn5Um97mDnYT9SJwMpDFS_FYBTKlsNhsVy11EW5kKuo6mTRlfebJa9CQv-NzbUajd7ulAcM4VNWYt-KbbhVZtUUUxgDvXJdlwRSYR5U8JwSze3sfatb5mbds-EAS-tT7grwrvTb4wRz20e9ARtBg6kC_x8QujHmFORJ97zrFlnnunPbsWgwWz8bfT9RMFy5xUE1KDCtnJqp-M3FoWwQc4sREIyCl7Q6JTq_slPe-Xwt9C5oquj4e_SoOuTAfqDPAmIG6rEXKSN7RP3KRjN5IA5Wpp2I0hgOJ6bT2qNAAUAAAAAAAAASAAAAAAAAAAKGkRhdGEtUHJvdG9jb2wEYW8QZnVuY3Rpb24GcmF3GkRhdGEtUHJvdG9jb2wEYW8OYW8tdHlwZQ5tZXNzYWdlBlNESwRhbwA2NTgz".to_string()).expect("failed to encode data item");
+        let data_item = DataItem::from_bytes(item_bytes).expect("failed to build data item");
+
+        let ordered = Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::Strict,
+            canonical_tag_order: vec!["function".to_string(), "SDK".to_string()],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec![],
+            allowed_signature_types: vec![]
+        }), Arc::new(MockLogger), VerificationMode::Strict);
+        assert!(ordered.verify_data_item(&data_item).await.is_ok());
+
+        let unordered = Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::Strict,
+            canonical_tag_order: vec!["SDK".to_string(), "function".to_string()],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec![],
+            allowed_signature_types: vec![]
+        }), Arc::new(MockLogger), VerificationMode::Strict);
+        assert!(unordered.verify_data_item(&data_item).await.is_err());
+
+        let unchecked = Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::Normalize,
+            canonical_tag_order: vec!["SDK".to_string(), "function".to_string()],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec![],
+            allowed_signature_types: vec![]
+        }), Arc::new(MockLogger), VerificationMode::Strict);
+        assert!(unchecked.verify_data_item(&data_item).await.is_ok());
+    }
+
+    fn shaped_item(tag_count: usize, data_len: usize) -> DataItem {
+        let tags = (0..tag_count).map(|i| Tag::new(&format!("Tag-{}", i), "x")).collect();
+        signed_item(vec![], vec![1; data_len], tags)
+    }
+
+    fn shape_verifier(shape_check_mode: ShapeCheckMode) -> Verifier {
+        Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::None,
+            canonical_tag_order: vec![],
+            shape_check_mode,
+            allowed_variants: vec![],
+            allowed_signature_types: vec![]
+        }), Arc::new(MockLogger), VerificationMode::Strict)
+    }
+
+    #[tokio::test]
+    async fn test_verify_shape_normal_item() {
+        let verifier = shape_verifier(ShapeCheckMode::Reject);
+        assert!(verifier.verify_data_item(&shaped_item(5, 10)).await.is_ok());
+        assert!(verifier.verify_data_item(&shaped_item(64, 4096)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_tag_limits() {
+        let verifier = shape_verifier(ShapeCheckMode::Off);
+        assert!(verifier.verify_data_item(&shaped_item(128, 10)).await.is_ok());
+
+        match verifier.verify_data_item(&shaped_item(129, 10)).await {
+            Err(VerifyErrorType::TagLimitExceeded(e)) => assert_eq!(e, "Data item has 129 tags, more than MAX_TAG_COUNT 128"),
+            _ => panic!("expected too many tags to be rejected")
+        }
+
+        let oversized = variant_item(vec![Tag::new("Large", &"x".repeat(3068))]);
+        match verifier.verify_data_item(&oversized).await {
+            Err(VerifyErrorType::TagLimitExceeded(e)) => assert_eq!(e, "Data item has 3073 bytes of tags, more than MAX_TAG_BYTES 3072"),
+            _ => panic!("expected oversized tags to be rejected")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_shape_many_tags_tiny_data() {
+        let item = shaped_item(100, 4);
+
+        let result = shape_verifier(ShapeCheckMode::Reject).verify_data_item(&item).await;
+        assert!(matches!(result, Err(VerifyErrorType::SuspiciousShape(_))));
+
+        let result = shape_verifier(ShapeCheckMode::Log).verify_data_item(&item).await;
+        assert!(result.is_ok());
+    }
+
+    fn variant_verifier() -> Verifier {
+        Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::None,
+            canonical_tag_order: vec![],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec!["ao.TN.1".to_string()],
+            allowed_signature_types: vec![]
+        }), Arc::new(MockLogger), VerificationMode::Strict)
+    }
+
+    fn variant_item(tags: Vec<Tag>) -> DataItem {
+        signed_item(vec![], b"data".to_vec(), tags)
+    }
+
+    #[tokio::test]
+    async fn test_verify_variant_accepted() {
+        let item = variant_item(vec![Tag::new("Variant", "ao.TN.1")]);
+        assert!(variant_verifier().verify_data_item(&item).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_variant_rejected() {
+        let unknown = variant_item(vec![Tag::new("Variant", "ao.LN.1")]);
+        assert!(variant_verifier().verify_data_item(&unknown).await.is_err());
+
+        let missing = variant_item(vec![Tag::new("Type", "Message")]);
+        assert!(variant_verifier().verify_data_item(&missing).await.is_err());
+    }
+
+    fn signature_type_verifier(allowed_signature_types: Vec<u16>) -> Verifier {
+        Verifier::new(Arc::new(MockGateway), Arc::new(MockConfig {
+            tag_order_mode: TagOrderMode::None,
+            canonical_tag_order: vec![],
+            shape_check_mode: ShapeCheckMode::Off,
+            allowed_variants: vec![],
+            allowed_signature_types
+        }), Arc::new(MockLogger), VerificationMode::Strict)
+    }
+
+    #[tokio::test]
+    async fn test_verify_signature_type_allowed() {
+        // items built here carry the arweave signature type
+        let item = variant_item(vec![Tag::new("Type", "Message")]);
+        assert_eq!(item.signature_type(), 1);
+        assert!(signature_type_verifier(vec![1, 3]).verify_data_item(&item).await.is_ok());
+        assert!(signature_type_verifier(vec![]).verify_data_item(&item).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_arweave_signature() {
+        let item = variant_item(vec![Tag::new("Type", "Message")]);
+        let mut forged = variant_item(vec![Tag::new("Type", "Other")]);
+        forged.signature = item.signature.clone();
+
+        // needs no round trip so lenient checks it too
+        for mode in [VerificationMode::Strict, VerificationMode::Lenient] {
+            let verifier = Verifier::new(Arc::new(MockGateway), mock_config(), Arc::new(MockLogger), mode);
+            assert!(verifier.verify_data_item(&item).await.is_ok());
+            let result = verifier.verify_data_item(&forged).await;
+            assert!(matches!(result, Err(VerifyErrorType::VerifyError(e)) if e == "Invalid signature"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_signature_type_not_allowed() {
+        let item = variant_item(vec![Tag::new("Type", "Message")]);
+        let result = signature_type_verifier(vec![3]).verify_data_item(&item).await;
+        assert!(matches!(result, Err(VerifyErrorType::SignatureTypeNotAllowed(1))));
+    }
+}
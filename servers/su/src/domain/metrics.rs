@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use crate::domain::core::dal::Metrics;
+
+/*
+Metrics used when no metrics backend is
+configured, every observation is discarded
+*/
+
+pub struct NoopMetrics;
+
+impl NoopMetrics {
+    pub fn init() -> Arc<dyn Metrics> {
+        Arc::new(NoopMetrics {})
+    }
+}
+
+impl Metrics for NoopMetrics {}
@@ -1,13 +1,22 @@
 
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 mod clients;
 mod core;
 mod logger;
+mod tracer;
+mod metrics;
+mod payment;
+mod audit;
 mod config;
 
 use core::dal::{Log, Gateway, Config};
 use logger::SuLog;
+use tracer::NoopTracer;
+use metrics::NoopMetrics;
+use payment::NoopPaymentVerifier;
+use audit::{NoopAuditSink, StoreAuditSink};
 use config::AoConfig;
 use clients::{
     store::StoreClient, 
@@ -20,9 +29,13 @@ use clients::{
 pub use core::flows;
 pub use flows::Deps;
 pub use core::router;
+pub use core::dal::{AckMode, SortOrder};
+pub use core::scheduler::compute_hash_chain;
 
 pub async fn init_deps(mode: Option<String>) -> Arc<Deps> {
     let logger: Arc<dyn Log> = SuLog::init();
+    let tracer = NoopTracer::init();
+    let metrics = NoopMetrics::init();
 
     let data_store = Arc::new(StoreClient::new().expect("Failed to create StoreClient"));
 
@@ -35,31 +48,52 @@ pub async fn init_deps(mode: Option<String>) -> Arc<Deps> {
 
     let scheduler_deps = Arc::new(core::scheduler::SchedulerDeps {
         data_store: data_store.clone(),
-        logger: logger.clone()
+        logger: logger.clone(),
+        config: config.clone()
     });
     let scheduler = Arc::new(core::scheduler::ProcessScheduler::new(scheduler_deps));
 
     let gateway: Arc<dyn Gateway> = Arc::new(
-        ArweaveGateway::new().await.expect("Failed to initialize gateway")
+        ArweaveGateway::new(config.gateway_url.clone(), Duration::from_secs(config.network_info_ttl_seconds)).await.expect("Failed to initialize gateway")
     );
 
     let signer = Arc::new(ArweaveSigner::new(&config.su_wallet_path).expect("Invalid su wallet path"));
     
     let wallet = Arc::new(FileWallet);
 
+    let audit = match config.audit_admin_actions {
+        true => StoreAuditSink::init(data_store.clone()),
+        false => NoopAuditSink::init()
+    };
+
     let uploader = Arc::new(
         UploaderClient::new(
             &config.upload_node_url, 
-            logger.clone()
+            logger.clone(),
+            config.upload_chunk_threshold
         ).expect("Invalid uploader url")
     );
 
+    let message_events = Arc::new(core::events::MessageEvents::new(config.message_subscription_buffer));
+
     Arc::new(
         Deps {
             data_store,
             logger,
+            tracer,
+            metrics,
             config,
             scheduler,
+            payment_verifier: NoopPaymentVerifier::init(),
+            audit,
+            load: Arc::new(core::load::LoadMonitor::new()),
+            log_sampler: Arc::default(),
+            head_cache: Arc::default(),
+            verification_cache: Arc::default(),
+            module_tags: Arc::default(),
+            epoch_roots: Arc::default(),
+            message_events,
+            boot_time: SystemTime::now(),
             gateway,
             signer,
             wallet,
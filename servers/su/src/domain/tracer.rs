@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use crate::domain::core::dal::Tracer;
+
+/*
+Tracer used when no tracing backend is
+configured, every span is discarded
+*/
+
+pub struct NoopTracer;
+
+impl NoopTracer {
+    pub fn init() -> Arc<dyn Tracer> {
+        Arc::new(NoopTracer {})
+    }
+}
+
+impl Tracer for NoopTracer {}
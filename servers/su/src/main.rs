@@ -1,14 +1,17 @@
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 use std::io::{self, Error, ErrorKind};
 
-use actix_web::{web, App, HttpResponse, HttpServer, Responder, HttpRequest, middleware::Logger, http::header::LOCATION};
+use actix_web::{web, App, HttpResponse, HttpServer, Responder, HttpRequest, middleware::Logger, http::header::{AUTHORIZATION, LOCATION}};
 use actix_cors::Cors;
+use futures::StreamExt;
 
 use serde_json::json;
 use serde::Deserialize;
 
-use su::domain::{Deps, init_deps, flows, router};
+use su::domain::{Deps, init_deps, flows, router, AckMode, SortOrder};
+use flows::{FlowErrorType, HealthStatus};
 
 #[derive(Deserialize)]
 struct FromTo {
@@ -17,6 +20,14 @@ struct FromTo {
     limit: Option<i32>,
     #[serde(rename = "process-id")]
     process_id: Option<String>,
+    // the signature preimage of a single message, large so off by default
+    #[serde(rename = "include-preimage")]
+    include_preimage: Option<bool>,
+    // messages past their process Message-TTL when EXPIRED_MESSAGE_MODE is filter
+    #[serde(rename = "include-expired")]
+    include_expired: Option<bool>,
+    // asc or desc by timestamp, asc when not set
+    order: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -35,6 +46,44 @@ struct ProcessIdRequired {
     process_id: String,
 }
 
+#[derive(Deserialize)]
+struct WriteParams {
+    // milliseconds the client is willing to wait
+    deadline: Option<u64>,
+    // a gateway url to read the block height from
+    #[serde(rename = "gateway-hint")]
+    gateway_hint: Option<String>,
+    // persisted responds once saved, uploaded once the upload is done too
+    #[serde(rename = "ack-mode")]
+    ack_mode: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Depth {
+    depth: Option<i32>,
+    #[serde(rename = "process-id")]
+    process_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AfterTime {
+    // a timestamp or the next_cursor of the last poll
+    after: String,
+    limit: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct ProcessEpoch {
+    process_id: String,
+    epoch: i32,
+}
+
+#[derive(Deserialize)]
+struct ProcessPair {
+    process_id: String,
+    other_process_id: String,
+}
+
 fn err_response(err: String) -> HttpResponse {
     let error_json = json!({ "error": err });
     HttpResponse::BadRequest()
@@ -42,6 +91,48 @@ fn err_response(err: String) -> HttpResponse {
         .body(error_json.to_string())
 }
 
+fn not_found_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::NotFound()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
+fn payment_required_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::PaymentRequired()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
+fn unavailable_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::ServiceUnavailable()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
+fn forbidden_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::Forbidden()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
+fn backend_error_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::InternalServerError()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
+fn misdirected_response(err: String) -> HttpResponse {
+    let error_json = json!({ "error": err });
+    HttpResponse::MisdirectedRequest()
+        .content_type("application/json") 
+        .body(error_json.to_string())
+}
+
 async fn base(deps: web::Data<Arc<Deps>>, query_params: web::Query<ProcessId>, req: HttpRequest) -> impl Responder {
     let process_id = query_params.process_id.clone();
 
@@ -54,8 +145,9 @@ async fn base(deps: web::Data<Arc<Deps>>, query_params: web::Query<ProcessId>, r
         Err(err) => return err_response(err.to_string())
     }
 
+    // the report is informational here, only /health signals a degraded su
     match flows::health(deps.get_ref().clone()).await {
-        Ok(processed_str) => HttpResponse::Ok()
+        Ok((_, processed_str)) => HttpResponse::Ok()
             .content_type("application/json")
             .body(processed_str),
         Err(err) => err_response(err.to_string()),
@@ -82,7 +174,7 @@ async fn timestamp_route(deps: web::Data<Arc<Deps>>, query_params: web::Query<Pr
     }
 }
 
-async fn main_post_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes, req: HttpRequest) -> impl Responder {
+async fn main_post_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes, req: HttpRequest, query_params: web::Query<WriteParams>) -> impl Responder {
     match router::redirect_data_item(deps.get_ref().clone(), req_body.to_vec()).await {
         Ok(Some(redirect_url)) => {
             let target_url = format!("{}{}", redirect_url, req.uri());
@@ -92,7 +184,38 @@ async fn main_post_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes, req:
         Err(err) => return err_response(err.to_string())
     }
 
-    match flows::write_item(deps.get_ref().clone(), req_body.to_vec()).await {
+    let deadline = query_params.deadline.map(Duration::from_millis);
+    let gateway_hint = query_params.gateway_hint.clone();
+    let ack_mode = match query_params.ack_mode.as_deref().map(str::parse::<AckMode>).transpose() {
+        Ok(ack_mode) => ack_mode,
+        Err(err) => return err_response(err)
+    };
+
+    match flows::write_item(deps.get_ref().clone(), req_body.to_vec(), deadline, gateway_hint, ack_mode).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::PaymentRequired(err)) => payment_required_response(err),
+        Err(FlowErrorType::Overloaded(err)) => unavailable_response(err),
+        Err(FlowErrorType::GatewayUnavailable(err)) => unavailable_response(err),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(FlowErrorType::WrongScheduler(err)) => misdirected_response(err),
+        Err(FlowErrorType::Backend(err)) => backend_error_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn estimate_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes) -> impl Responder {
+    match flows::estimate_upload_cost(deps.get_ref().clone(), req_body.to_vec()).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn validate_bundle_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes) -> impl Responder {
+    match flows::validate_bundle_structure(deps.get_ref().clone(), req_body.to_vec()).await {
         Ok(processed_str) => HttpResponse::Ok()
             .content_type("application/json")
             .body(processed_str),
@@ -100,12 +223,27 @@ async fn main_post_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes, req:
     }
 }
 
+async fn validate_item_route(deps: web::Data<Arc<Deps>>, req_body: web::Bytes) -> impl Responder {
+    match flows::validate_item(deps.get_ref().clone(), req_body.to_vec()).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.into()),
+    }
+}
+
 async fn main_get_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<TxId>, query_params: web::Query<FromTo>) -> impl Responder {
     let tx_id = path.tx_id.clone();
     let from_sort_key = query_params.from.clone();
     let to_sort_key = query_params.to.clone();
     let limit = query_params.limit.clone();
     let process_id = query_params.process_id.clone();
+    let include_preimage = query_params.include_preimage.unwrap_or(false);
+    let include_expired = query_params.include_expired.unwrap_or(false);
+    let order = match query_params.order.as_deref().map(str::parse::<SortOrder>).transpose() {
+        Ok(order) => order,
+        Err(err) => return err_response(err)
+    };
 
     match router::redirect_tx_id(deps.get_ref().clone(), tx_id.clone(), process_id.clone()).await {
         Ok(Some(redirect_url)) => {
@@ -116,13 +254,15 @@ async fn main_get_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web:
         Err(err) => return err_response(err.to_string())
     }
 
-    let result = flows::read_message_data(deps.get_ref().clone(), tx_id, from_sort_key, to_sort_key, limit).await;
+    let result = flows::read_message_data(deps.get_ref().clone(), tx_id, from_sort_key, to_sort_key, limit, order, include_preimage, include_expired).await;
 
     match result {
         Ok(processed_str) => HttpResponse::Ok()
             .content_type("application/json")
             .body(processed_str),
-        Err(err) => err_response(err.to_string()),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(FlowErrorType::Backend(err)) => backend_error_response(err),
+        Err(err) => err_response(err.into()),
     }
 }
 
@@ -146,8 +286,330 @@ async fn read_process_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path:
     }
 }
 
-async fn health_check() -> impl Responder {
+async fn read_messages_gql_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>, query_params: web::Query<FromTo>) -> impl Responder {
+    let process_id = path.process_id.clone();
+    let from_sort_key = query_params.from.clone();
+    let to_sort_key = query_params.to.clone();
+    let limit = query_params.limit;
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_messages_gql(deps.get_ref().clone(), process_id, from_sort_key, to_sort_key, limit).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn read_process_binary_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_process_binary(deps.get_ref().clone(), process_id).await {
+        Ok(binary) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(binary),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn read_epoch_root_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessEpoch>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_epoch_root(deps.get_ref().clone(), process_id, path.epoch).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn poll_messages_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>, query_params: web::Query<AfterTime>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_messages_after_time(deps.get_ref().clone(), process_id, query_params.after.clone(), query_params.limit).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn read_owner_stats_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_process_owner_stats(deps.get_ref().clone(), process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn read_process_full_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_process_full(deps.get_ref().clone(), process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+// server sent events of each message of the process as it is saved
+async fn subscribe_messages_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    let events = match flows::subscribe_messages(deps.get_ref().clone(), process_id).await {
+        Ok(events) => events,
+        Err(FlowErrorType::NotFound(err)) => return not_found_response(err),
+        Err(FlowErrorType::Backend(err)) => return backend_error_response(err),
+        Err(err) => return err_response(err.into())
+    };
+    let events = events.map(|message| {
+        let data = serde_json::to_string(&message).map_err(actix_web::error::ErrorInternalServerError)?;
+        Ok::<web::Bytes, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", data)))
+    });
     HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+async fn read_epoch_counts_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_epoch_message_counts(deps.get_ref().clone(), process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn latest_message_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::latest(deps.get_ref().clone(), process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn message_count_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessIdRequired>) -> impl Responder {
+    let process_id = path.process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::message_count(deps.get_ref().clone(), process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+async fn diff_processes_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<ProcessPair>) -> impl Responder {
+    let process_id = path.process_id.clone();
+    let other_process_id = path.other_process_id.clone();
+
+    match router::redirect_process_id(deps.get_ref().clone(), Some(process_id.clone())).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::diff_processes(deps.get_ref().clone(), process_id, other_process_id).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn message_ancestry_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<TxId>, query_params: web::Query<Depth>) -> impl Responder {
+    let tx_id = path.tx_id.clone();
+    let depth = query_params.depth;
+    let process_id = query_params.process_id.clone();
+
+    match router::redirect_tx_id(deps.get_ref().clone(), tx_id.clone(), process_id).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_message_ancestry(deps.get_ref().clone(), tx_id, depth).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn read_inner_item_route(deps: web::Data<Arc<Deps>>, req: HttpRequest, path: web::Path<TxId>, query_params: web::Query<ProcessId>) -> impl Responder {
+    let tx_id = path.tx_id.clone();
+    let process_id = query_params.process_id.clone();
+
+    match router::redirect_tx_id(deps.get_ref().clone(), tx_id.clone(), process_id).await {
+        Ok(Some(redirect_url)) => {
+            let target_url = format!("{}{}", redirect_url, req.uri());
+            return HttpResponse::TemporaryRedirect().insert_header((LOCATION, target_url)).finish();
+        },
+        Ok(None) => (),
+        Err(err) => return err_response(err.to_string())
+    }
+
+    match flows::read_inner_item(deps.get_ref().clone(), tx_id).await {
+        Ok(binary) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(binary),
+        Err(FlowErrorType::NotFound(err)) => not_found_response(err),
+        Err(err) => err_response(err.into()),
+    }
+}
+
+// the token of an Authorization: Bearer header
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers().get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")
+}
+
+async fn read_config_route(deps: web::Data<Arc<Deps>>, req: HttpRequest) -> impl Responder {
+    if !flows::is_admin(deps.get_ref(), bearer_token(&req)) {
+        return forbidden_response("Admin token required".to_string());
+    }
+    match flows::read_config(deps.get_ref().clone()).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
+}
+
+async fn unique_ids_route(deps: web::Data<Arc<Deps>>, req: HttpRequest) -> impl Responder {
+    if !flows::is_admin(deps.get_ref(), bearer_token(&req)) {
+        return forbidden_response("Admin token required".to_string());
+    }
+    match flows::check_message_id_uniqueness(deps.get_ref().clone()).await {
+        Ok(processed_str) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => backend_error_response(err),
+    }
+}
+
+// a dependency is down, load balancers should stop sending here
+async fn health_check(deps: web::Data<Arc<Deps>>) -> impl Responder {
+    match flows::health(deps.get_ref().clone()).await {
+        Ok((status, processed_str)) => match status {
+            HealthStatus::Ok => HttpResponse::Ok(),
+            HealthStatus::Degraded => HttpResponse::ServiceUnavailable()
+        }
+            .content_type("application/json")
+            .body(processed_str),
+        Err(err) => err_response(err.to_string()),
+    }
 }
 
 #[actix_web::main]
@@ -176,6 +638,16 @@ async fn main() -> io::Result<()> {
 
     let run_deps = wrapped.get_ref().clone();
 
+    if run_deps.config.self_test_on_startup() {
+        match flows::self_test(run_deps.clone()).await {
+            Ok(m) => run_deps.logger.log(format!("signer self test passed - {}", m)),
+            Err(e) => {
+                let message: String = e.into();
+                return Err(Error::other(message));
+            }
+        }
+    }
+
     if run_deps.config.mode() == "router" {
         match router::init_schedulers(run_deps.clone()).await {
             Err(e) => run_deps.logger.log(format!("{}", e)),
@@ -196,9 +668,27 @@ async fn main() -> io::Result<()> {
             .route("/", web::get().to(base))
             .route("/", web::post().to(main_post_route)) 
             .route("/timestamp", web::get().to(timestamp_route))
+            .route("/estimate", web::post().to(estimate_route))
+            .route("/validate-bundle", web::post().to(validate_bundle_route))
+            .route("/validate", web::post().to(validate_item_route))
             .route("/health", web::get().to(health_check))
+            .route("/config", web::get().to(read_config_route))
+            .route("/admin/unique-ids", web::get().to(unique_ids_route))
             .route("/{tx_id}", web::get().to(main_get_route))
+            .route("/{tx_id}/ancestry", web::get().to(message_ancestry_route))
+            .route("/{tx_id}/item", web::get().to(read_inner_item_route))
             .route("/processes/{process_id}", web::get().to(read_process_route))
+            .route("/processes/{process_id}/full", web::get().to(read_process_full_route))
+            .route("/processes/{process_id}/subscribe", web::get().to(subscribe_messages_route))
+            .route("/processes/{process_id}/graphql", web::get().to(read_messages_gql_route))
+            .route("/processes/{process_id}/binary", web::get().to(read_process_binary_route))
+            .route("/processes/{process_id}/owners", web::get().to(read_owner_stats_route))
+            .route("/processes/{process_id}/poll", web::get().to(poll_messages_route))
+            .route("/processes/{process_id}/latest", web::get().to(latest_message_route))
+            .route("/processes/{process_id}/count", web::get().to(message_count_route))
+            .route("/processes/{process_id}/epochs", web::get().to(read_epoch_counts_route))
+            .route("/processes/{process_id}/epochs/{epoch}/root", web::get().to(read_epoch_root_route))
+            .route("/processes/{process_id}/diff/{other_process_id}", web::get().to(diff_processes_route))
     })
     .bind(("0.0.0.0", port))?
     .run()